@@ -13,6 +13,20 @@ use alvearium::database_api::get_tx_sign_properties;
 use alvearium::HttpClient;
 use napi::bindgen_prelude::{Error, Result, Status};
 
+/// Error for JS with the messages of `e` and its sources, JS only gets the text
+fn failure(e: &dyn std::error::Error) -> Error {
+    let mut message = e.to_string();
+    let mut source = e.source();
+
+    while let Some(e) = source {
+        message.push_str(": ");
+        message.push_str(&e.to_string());
+        source = e.source();
+    }
+
+    Error::new(Status::GenericFailure, message)
+}
+
 #[napi(js_name = "HiveClient")]
 pub struct JsClient {
     client: HttpClient,
@@ -48,30 +62,25 @@ impl JsClient {
 
     #[napi]
     pub async fn broadcast_custom_json(&self, custom_json: CustomJson, key: String) -> Result<()> {
-        let sk = PrivateKey::from_wif(key).map_err(|_| {
+        let sk = PrivateKey::from_wif(key).map_err(|e| {
             Error::new(
                 Status::GenericFailure,
-                "Private key provided is not valid WIF format".to_owned(),
+                format!("Private key provided is not valid WIF format: {}", e),
             )
         })?;
 
         let properties = get_tx_sign_properties(&self.client, BlockchainMode::Reversible)
             .await
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+            .map_err(|e| failure(&e))?;
         let transaction =
             UnsignedTransaction::new(&properties, vec![Operation::CustomJson(custom_json.into())])
-                .map_err(|_| {
-                    Error::new(
-                        Status::GenericFailure,
-                        "Failed to construct transaction".to_owned(),
-                    )
-                })?;
+                .map_err(|e| failure(&e))?;
 
-        let signed = transaction.sign(&sk, None);
+        let signed = transaction.sign(&sk, None).map_err(|e| failure(&e))?;
 
         broadcast_transaction(&self.client, &vec![signed])
             .await
-            .map_err(|e| Error::new(Status::GenericFailure, e.to_string()))?;
+            .map_err(|e| failure(&e))?;
 
         Ok(())
     }
@@ -0,0 +1,22 @@
+use alvearium::block_log::BlockLog;
+
+fn main() -> anyhow::Result<()> {
+    let path = std::env::args()
+        .nth(1)
+        .unwrap_or_else(|| "blockchain/block_log".to_owned());
+    let mut block_log = BlockLog::open(path)?;
+    let head = block_log.head_block_num()?;
+    println!("block_log ends at block {}", head);
+
+    for block in block_log.blocks(head.saturating_sub(10)..head + 1) {
+        let block = block?;
+        println!(
+            "Block {:?} by {} with {} transactions",
+            block.block_num(),
+            block.witness,
+            block.transactions.len()
+        );
+    }
+
+    Ok(())
+}
@@ -2,13 +2,12 @@ use alvearium::condenser_api::broadcast_transaction;
 use alvearium::condenser_api::operation::Operation;
 use alvearium::condenser_api::transaction::BlockchainMode;
 use alvearium::condenser_api::transaction::UnsignedTransaction;
+use alvearium::crypto::key_set::{generate_password, KeySet};
 use alvearium::crypto::private_key::PrivateKey;
 use alvearium::crypto::FromWif;
 use alvearium::database_api::{get_tx_sign_properties, TxSignProperties};
-use alvearium::operation::{AccountCreate, Asset, AssetSymbol, AuthorityType, CustomJson};
+use alvearium::operation::{AccountCreate, Asset, AssetSymbol, CustomJson};
 use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
-use rand::rngs::OsRng;
-use secp256k1::{All, Secp256k1};
 use tracing_subscriber::util::SubscriberInitExt;
 
 async fn submit_custom_json(client: &HttpClient, props: &TxSignProperties) -> anyhow::Result<()> {
@@ -27,7 +26,7 @@ async fn submit_custom_json(client: &HttpClient, props: &TxSignProperties) -> an
     )
     .unwrap();
 
-    let signed = trx.sign(&private_key, None);
+    let signed = trx.sign(&private_key, None)?;
 
     match broadcast_transaction(&client, &vec![signed]).await {
         Ok(res) => println!("{:?}", res),
@@ -37,19 +36,6 @@ async fn submit_custom_json(client: &HttpClient, props: &TxSignProperties) -> an
     Ok(())
 }
 
-fn key_to_authority_type(key: PrivateKey) -> AuthorityType {
-    AuthorityType {
-        weight_threshold: 1,
-        account_auths: vec![],
-        key_auths: vec![(key.create_public(None), 1)],
-    }
-}
-
-fn create_key(secp: &Secp256k1<All>) -> PrivateKey {
-    let (key, _) = secp.generate_keypair(&mut OsRng);
-    PrivateKey::from_key(key, None)
-}
-
 async fn submit_account_create(
     client: &HttpClient,
     props: &TxSignProperties,
@@ -58,24 +44,24 @@ async fn submit_account_create(
         PrivateKey::from_wif(std::env::var("PRIV_KEY").expect("PRIV_KEY env var missing")).unwrap();
     let hive_name = std::env::var("HIVE_NAME").expect("HIVE_NAME env var missing");
 
-    let secp = Secp256k1::new();
+    let new_account_name = "orillion50".to_string();
+    let password = generate_password();
+    let keys = KeySet::from_password(&new_account_name, &password)?;
+    println!("Password for {}: {}", new_account_name, password);
 
     let trx = UnsignedTransaction::new(
         &props,
-        vec![Operation::AccountCreate(AccountCreate {
-            creator: hive_name.to_string(),
-            owner: key_to_authority_type(create_key(&secp)),
-            active: key_to_authority_type(create_key(&secp)),
-            posting: key_to_authority_type(create_key(&secp)),
-            memo_key: create_key(&secp).create_public(None),
-            new_account_name: "orillion50".to_string(),
-            fee: Asset::new(100, AssetSymbol::HIVE),
-            json_metadata: "{}".to_string(),
-        })],
+        vec![Operation::AccountCreate(AccountCreate::new(
+            Asset::new(100, AssetSymbol::HIVE),
+            hive_name.to_string(),
+            new_account_name,
+            keys.create_public(None),
+            "{}".to_string(),
+        ))],
     )
     .unwrap();
 
-    let signed = trx.sign(&private_key, None);
+    let signed = trx.sign(&private_key, None)?;
 
     match broadcast_transaction(&client, &vec![signed]).await {
         Ok(res) => println!("{:?}", res),
@@ -0,0 +1,21 @@
+use alvearium::block_api::subscribe_new_blocks;
+use alvearium::create_ws_client;
+use futures::StreamExt;
+
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
+    let client = create_ws_client("wss://api.hive.blog:443").await?;
+    let mut blocks = Box::pin(subscribe_new_blocks(&client));
+
+    while let Some(block) = blocks.next().await {
+        let block = block?;
+        println!(
+            "Block {:?} by {} with {} transactions",
+            block.block_num(),
+            block.witness,
+            block.transactions.len()
+        );
+    }
+
+    Ok(())
+}
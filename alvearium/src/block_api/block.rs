@@ -1,11 +1,21 @@
 use crate::block_api::transaction::Transaction;
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{is_low_s, sha224, signature_from_bytes, IntoWif};
 use crate::de::deserialize_hive_time;
+use crate::dec::{decode_leb128, decode_without_size, DecodeError};
+use crate::enc::{encode_to_vec, EncodeError, Writer, LEB128};
+use crate::{HiveDecode, HiveDecoder, HiveEncode, HiveEncoder};
 use chrono::{DateTime, Utc};
 use serde::Deserialize;
+use serde_json::Value;
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
 
 #[derive(Deserialize, Debug)]
 pub struct BlockResponse {
-    pub block: Block,
+    /// `None` if the block does not exist yet
+    #[serde(default)]
+    pub block: Option<Block>,
 }
 
 #[derive(Deserialize, Debug)]
@@ -15,12 +25,44 @@ pub struct Block {
     pub timestamp: DateTime<Utc>,
     pub witness: String,
     pub transaction_merkle_root: String,
+    #[serde(default)]
+    pub extensions: Vec<BlockHeaderExtension>,
     pub witness_signature: String,
     pub transactions: Vec<Transaction>,
     pub block_id: String,
     pub signing_key: String,
     pub transaction_ids: Vec<String>,
-    // TODO: Extensions?
+}
+
+impl Block {
+    /// Number of the block, taken from the first four bytes of `block_id`
+    pub fn block_num(&self) -> Option<u32> {
+        block_num_from_id(&self.block_id)
+    }
+
+    /// The header of the block, the part the witness signs
+    pub fn header(&self) -> BlockHeader {
+        BlockHeader {
+            previous: self.previous.clone(),
+            timestamp: self.timestamp,
+            witness: self.witness.clone(),
+            transaction_merkle_root: self.transaction_merkle_root.clone(),
+            extensions: self.extensions.clone(),
+        }
+    }
+
+    /// The header of the block with the signature of the witness
+    pub fn signed_header(&self) -> SignedBlockHeader {
+        SignedBlockHeader {
+            header: self.header(),
+            witness_signature: self.witness_signature.clone(),
+        }
+    }
+}
+
+/// Returns the number of the block a block id belongs to
+pub(crate) fn block_num_from_id(block_id: &str) -> Option<u32> {
+    u32::from_str_radix(block_id.get(0..8)?, 16).ok()
 }
 
 #[derive(Deserialize, Debug)]
@@ -28,17 +70,501 @@ pub struct BlockHeaderResponse {
     pub header: BlockHeader,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, Clone)]
 pub struct BlockHeader {
     pub previous: String,
     #[serde(deserialize_with = "deserialize_hive_time")]
     pub timestamp: DateTime<Utc>,
     pub witness: String,
     pub transaction_merkle_root: String,
-    // TODO: Extensions?
+    #[serde(default)]
+    pub extensions: Vec<BlockHeaderExtension>,
 }
 
 #[derive(Deserialize, Debug)]
 pub struct BlockRangeResponse {
-    pub blocks: Vec<BlockHeader>,
+    pub blocks: Vec<Block>,
+}
+
+/// Decodes a hex encoded hash of `N` bytes, e.g. a block id
+///
+/// # Arguments
+/// * `field` - Name of the field, for the error
+/// * `hash` - Hex to decode
+pub(crate) fn decode_hash<const N: usize>(field: &str, hash: &str) -> Result<[u8; N], EncodeError> {
+    hex::decode(hash)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| EncodeError::InvalidValue(format!("malformed {} {}", field, hash)))
+}
+
+impl HiveEncode for BlockHeader {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        let previous: [u8; 20] = decode_hash("previous", &self.previous)?;
+        let merkle_root: [u8; 20] =
+            decode_hash("transaction_merkle_root", &self.transaction_merkle_root)?;
+
+        encoder.writer().write(&previous)?;
+        self.timestamp.encode(encoder)?;
+        self.witness.encode(encoder)?;
+        encoder.writer().write(&merkle_root)?;
+        self.extensions.encode(encoder)
+    }
+}
+
+impl HiveDecode for BlockHeader {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(BlockHeader {
+            previous: hex::encode(decode_without_size::<_, 20>(decoder)?),
+            timestamp: DateTime::decode(decoder)?,
+            witness: String::decode(decoder)?,
+            transaction_merkle_root: hex::encode(decode_without_size::<_, 20>(decoder)?),
+            extensions: Vec::decode(decoder)?,
+        })
+    }
+}
+
+/// Block header with the signature of the witness that produced the block
+#[derive(Debug, Clone)]
+pub struct SignedBlockHeader {
+    pub header: BlockHeader,
+    pub witness_signature: String,
+}
+
+impl SignedBlockHeader {
+    /// Computes the id of the block.
+    ///
+    /// Like hived this is the SHA-224 of the signed header truncated to 20 bytes, with the first 4
+    /// bytes replaced by the big endian block number.
+    pub fn id(&self) -> Result<String, EncodeError> {
+        let block_num = block_num_from_id(&self.header.previous)
+            .ok_or_else(|| {
+                EncodeError::InvalidValue(format!("malformed previous {}", self.header.previous))
+            })?
+            .wrapping_add(1);
+
+        let mut hash = sha224(encode_to_vec(self)?);
+        hash[..4].copy_from_slice(&block_num.to_be_bytes());
+
+        Ok(hex::encode(&hash[..20]))
+    }
+
+    /// Recovers the key the header was signed with, `None` if the signature is malformed or not
+    /// canonical
+    pub fn signee(&self) -> Result<Option<PublicKey>, EncodeError> {
+        let signature = hex::decode(&self.witness_signature)
+            .ok()
+            .and_then(|bytes| signature_from_bytes(&bytes))
+            .filter(is_low_s);
+
+        Ok(match signature {
+            Some(signature) => PublicKey::recover(encode_to_vec(&self.header)?, &signature),
+            None => None,
+        })
+    }
+}
+
+impl HiveEncode for SignedBlockHeader {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        let signature: [u8; 65] = decode_hash("witness_signature", &self.witness_signature)?;
+
+        self.header.encode(encoder)?;
+        encoder.writer().write(&signature)
+    }
+}
+
+impl HiveDecode for SignedBlockHeader {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(SignedBlockHeader {
+            header: BlockHeader::decode(decoder)?,
+            witness_signature: hex::encode(decode_without_size::<_, 65>(decoder)?),
+        })
+    }
+}
+
+/// Block as hived stores it in its `block_log`, without the ids `block_api` adds
+#[derive(Debug)]
+pub struct SignedBlock {
+    pub signed_header: SignedBlockHeader,
+    pub transactions: Vec<Transaction>,
+}
+
+impl SignedBlock {
+    /// Adds the block id, signing key and transaction ids, as `block_api` returns the block
+    pub fn into_block(self) -> Result<Block, EncodeError> {
+        let block_id = self.signed_header.id()?;
+        let signing_key = self.signed_header.signee()?.ok_or_else(|| {
+            EncodeError::InvalidValue(format!(
+                "malformed witness_signature {}",
+                self.signed_header.witness_signature
+            ))
+        })?;
+        let transaction_ids = self
+            .transactions
+            .iter()
+            .map(Transaction::id)
+            .collect::<Result<_, _>>()?;
+        let SignedBlockHeader {
+            header,
+            witness_signature,
+        } = self.signed_header;
+
+        Ok(Block {
+            previous: header.previous,
+            timestamp: header.timestamp,
+            witness: header.witness,
+            transaction_merkle_root: header.transaction_merkle_root,
+            extensions: header.extensions,
+            witness_signature,
+            transactions: self.transactions,
+            block_id,
+            signing_key: signing_key.to_wif(),
+            transaction_ids,
+        })
+    }
+}
+
+impl From<Block> for SignedBlock {
+    fn from(block: Block) -> Self {
+        SignedBlock {
+            signed_header: SignedBlockHeader {
+                header: BlockHeader {
+                    previous: block.previous,
+                    timestamp: block.timestamp,
+                    witness: block.witness,
+                    transaction_merkle_root: block.transaction_merkle_root,
+                    extensions: block.extensions,
+                },
+                witness_signature: block.witness_signature,
+            },
+            transactions: block.transactions,
+        }
+    }
+}
+
+impl HiveEncode for SignedBlock {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.signed_header.encode(encoder)?;
+        self.transactions.encode(encoder)
+    }
+}
+
+impl HiveDecode for SignedBlock {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(SignedBlock {
+            signed_header: SignedBlockHeader::decode(decoder)?,
+            transactions: Vec::decode(decoder)?,
+        })
+    }
+}
+
+/// Version of hived, e.g. `1.27.0`, packed like fc's `version` as major, minor and patch in 8, 8
+/// and 16 bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub struct BlockchainVersion(pub u32);
+
+impl BlockchainVersion {
+    pub fn new(major: u8, minor: u8, patch: u16) -> Self {
+        Self((major as u32) << 24 | (minor as u32) << 16 | patch as u32)
+    }
+
+    pub fn major(&self) -> u8 {
+        (self.0 >> 24) as u8
+    }
+
+    pub fn minor(&self) -> u8 {
+        (self.0 >> 16) as u8
+    }
+
+    pub fn patch(&self) -> u16 {
+        self.0 as u16
+    }
+}
+
+impl Display for BlockchainVersion {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}.{}.{}", self.major(), self.minor(), self.patch())
+    }
+}
+
+impl FromStr for BlockchainVersion {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let parts: Vec<&str> = s.split('.').collect();
+        let invalid = || format!("invalid version {}", s);
+
+        match parts[..] {
+            [major, minor, patch] => Ok(Self::new(
+                major.parse().map_err(|_| invalid())?,
+                minor.parse().map_err(|_| invalid())?,
+                patch.parse().map_err(|_| invalid())?,
+            )),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl HiveEncode for BlockchainVersion {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.0.encode(encoder)
+    }
+}
+
+impl HiveDecode for BlockchainVersion {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(Self(u32::decode(decoder)?))
+    }
+}
+
+impl TryFrom<String> for BlockchainVersion {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+/// Extension of a block header, used by witnesses to signal their version and hardfork votes
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "RawExtension")]
+pub enum BlockHeaderExtension {
+    Void,
+    /// Version of hived the witness runs
+    Version(BlockchainVersion),
+    /// Hardfork the witness votes for and when it should be applied
+    HardforkVersionVote {
+        hf_version: BlockchainVersion,
+        hf_time: DateTime<Utc>,
+    },
+}
+
+impl HiveEncode for BlockHeaderExtension {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        match self {
+            BlockHeaderExtension::Void => LEB128::from(0u8).encode(encoder),
+            BlockHeaderExtension::Version(version) => {
+                LEB128::from(1u8).encode(encoder)?;
+                version.encode(encoder)
+            }
+            BlockHeaderExtension::HardforkVersionVote {
+                hf_version,
+                hf_time,
+            } => {
+                LEB128::from(2u8).encode(encoder)?;
+                hf_version.encode(encoder)?;
+                hf_time.encode(encoder)
+            }
+        }
+    }
+}
+
+impl HiveDecode for BlockHeaderExtension {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        match decode_leb128(decoder)? {
+            0 => Ok(BlockHeaderExtension::Void),
+            1 => Ok(BlockHeaderExtension::Version(BlockchainVersion::decode(
+                decoder,
+            )?)),
+            2 => Ok(BlockHeaderExtension::HardforkVersionVote {
+                hf_version: BlockchainVersion::decode(decoder)?,
+                hf_time: DateTime::decode(decoder)?,
+            }),
+            index => Err(DecodeError::InvalidValue(format!(
+                "block header extension {}",
+                index
+            ))),
+        }
+    }
+}
+
+/// Extension as sent by the node, either `{"type": "version", "value": ...}` or the legacy
+/// `[1, ...]`
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawExtension {
+    Object {
+        #[serde(rename = "type")]
+        kind: String,
+        value: Value,
+    },
+    Array(u8, Value),
+}
+
+#[derive(Deserialize)]
+struct HardforkVersionVote {
+    hf_version: BlockchainVersion,
+    #[serde(deserialize_with = "deserialize_hive_time")]
+    hf_time: DateTime<Utc>,
+}
+
+impl TryFrom<RawExtension> for BlockHeaderExtension {
+    type Error = String;
+
+    fn try_from(extension: RawExtension) -> Result<Self, Self::Error> {
+        let (kind, value) = match extension {
+            RawExtension::Object { kind, value } => (kind, value),
+            RawExtension::Array(0, value) => ("void_t".to_owned(), value),
+            RawExtension::Array(1, value) => ("version".to_owned(), value),
+            RawExtension::Array(2, value) => ("hardfork_version_vote".to_owned(), value),
+            RawExtension::Array(index, _) => return Err(format!("unknown extension {}", index)),
+        };
+
+        match kind.as_str() {
+            "void_t" => Ok(BlockHeaderExtension::Void),
+            "version" => Ok(BlockHeaderExtension::Version(
+                serde_json::from_value(value).map_err(|e| e.to_string())?,
+            )),
+            "hardfork_version_vote" => {
+                let vote: HardforkVersionVote =
+                    serde_json::from_value(value).map_err(|e| e.to_string())?;
+                Ok(BlockHeaderExtension::HardforkVersionVote {
+                    hf_version: vote.hf_version,
+                    hf_time: vote.hf_time,
+                })
+            }
+            kind => Err(format!("unknown extension {}", kind)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::block::{
+        BlockHeader, BlockHeaderExtension, BlockchainVersion, SignedBlock, SignedBlockHeader,
+    };
+    use crate::block_api::tests::block_id;
+    use crate::block_api::verify::tests::{signed_block, transfer_json};
+    use crate::dec::decode_from_slice;
+    use crate::enc::encode_to_vec;
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    #[test]
+    fn blockchain_version() {
+        let version: BlockchainVersion = "1.27.3".parse().unwrap();
+
+        assert_eq!(version, BlockchainVersion::new(1, 27, 3));
+        assert_eq!(version.0, 0x011b0003);
+        assert_eq!(version.to_string(), "1.27.3");
+        assert!("1.27".parse::<BlockchainVersion>().is_err());
+    }
+
+    #[test]
+    fn header_extensions() {
+        let header: BlockHeader = serde_json::from_value(json!({
+            "previous": "04c1c7a566fc0da66aee465714acee7346b48ac2",
+            "timestamp": "2020-10-06T13:28:57",
+            "witness": "blocktrades",
+            "transaction_merkle_root": "0000000000000000000000000000000000000000",
+            "extensions": [
+                { "type": "version", "value": "1.27.0" },
+                {
+                    "type": "hardfork_version_vote",
+                    "value": { "hf_version": "1.27.0", "hf_time": "2022-10-11T12:00:00" }
+                },
+                [1, "0.23.0"],
+                [0, {}]
+            ]
+        }))
+        .unwrap();
+
+        assert_eq!(
+            header.extensions,
+            vec![
+                BlockHeaderExtension::Version(BlockchainVersion::new(1, 27, 0)),
+                BlockHeaderExtension::HardforkVersionVote {
+                    hf_version: BlockchainVersion::new(1, 27, 0),
+                    hf_time: Utc.with_ymd_and_hms(2022, 10, 11, 12, 0, 0).unwrap(),
+                },
+                BlockHeaderExtension::Version(BlockchainVersion::new(0, 23, 0)),
+                BlockHeaderExtension::Void,
+            ]
+        );
+    }
+
+    #[test]
+    fn encode_signed_block() {
+        let header: BlockHeader = serde_json::from_value(json!({
+            "previous": block_id(99, 0),
+            "timestamp": "2022-10-20T10:00:03",
+            "witness": "witness",
+            "transaction_merkle_root": "0000000000000000000000000000000000000000",
+            "extensions": [
+                { "type": "version", "value": "1.27.0" },
+                {
+                    "type": "hardfork_version_vote",
+                    "value": { "hf_version": "1.27.0", "hf_time": "2022-10-11T12:00:00" }
+                }
+            ]
+        }))
+        .unwrap();
+        let block = SignedBlock {
+            signed_header: SignedBlockHeader {
+                header,
+                witness_signature: format!("1f{}", "ab".repeat(64)),
+            },
+            transactions: vec![],
+        };
+
+        let bytes = encode_to_vec(&block).unwrap();
+
+        assert_eq!(
+            hex::encode(&bytes),
+            [
+                &block_id(99, 0),
+                "231c5163",
+                "077769746e657373",
+                "0000000000000000000000000000000000000000",
+                "02",
+                "0100001b01",
+                "0200001b01c05a4563",
+                &format!("1f{}", "ab".repeat(64)),
+                "00"
+            ]
+            .concat()
+        );
+
+        let (decoded, len) = decode_from_slice::<SignedBlock>(&bytes).unwrap();
+        assert_eq!(len, bytes.len());
+        assert_eq!(decoded.signed_header.header.previous, block_id(99, 0));
+        assert_eq!(
+            decoded.signed_header.header.extensions,
+            block.signed_header.header.extensions
+        );
+        assert_eq!(encode_to_vec(&decoded).unwrap(), bytes);
+    }
+
+    #[test]
+    fn decode_into_block() {
+        let block = signed_block(
+            &block_id(99, 0),
+            (0..3).map(|i| transfer_json(&i.to_string())).collect(),
+        );
+        let (block_id, transaction_ids) = (block.block_id.clone(), block.transaction_ids.clone());
+        let bytes = encode_to_vec(SignedBlock::from(block)).unwrap();
+
+        let (decoded, _) = decode_from_slice::<SignedBlock>(&bytes).unwrap();
+        assert_eq!(encode_to_vec(&decoded).unwrap(), bytes);
+
+        let block = decoded.into_block().unwrap();
+        assert_eq!(block.block_id, block_id);
+        assert_eq!(block.transaction_ids, transaction_ids);
+        assert_eq!(
+            block.transactions[2].operations[0].accounts(),
+            vec!["alice", "bob"]
+        );
+        assert!(block.verify().is_ok());
+    }
+
+    #[test]
+    fn decode_truncated_block() {
+        let block = signed_block(&block_id(99, 0), vec![transfer_json("")]);
+        let bytes = encode_to_vec(SignedBlock::from(block)).unwrap();
+
+        for len in [0, 20, 100, bytes.len() - 1] {
+            assert!(decode_from_slice::<SignedBlock>(&bytes[..len]).is_err());
+        }
+    }
 }
@@ -0,0 +1,1136 @@
+//! Binary encoding of operations as hived packs them.
+//!
+//! Most operations of `block_api` are kept as JSON, so they are encoded and decoded by walking
+//! the JSON along a description of the C++ types instead of implementing `HiveEncode` and
+//! `HiveDecode` for every operation.
+
+use crate::block_api::block::BlockchainVersion;
+use crate::block_api::operation::Operation;
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{FromWif, IntoWif};
+use crate::dec::{decode_leb128, decode_without_size, DecodeError, Reader};
+use crate::enc::{EncodeError, Writer, LEB128};
+use crate::{HiveDecode, HiveDecoder, HiveEncode, HiveEncoder};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Map, Value};
+use std::str::FromStr;
+
+/// Types of the fields of operations, named after their C++ counterparts
+pub(crate) enum Type {
+    Bool,
+    U8,
+    U16,
+    U32,
+    U64,
+    I16,
+    I64,
+    String,
+    /// `vector<char>`, hex in JSON
+    Bytes,
+    /// Fixed size hash, e.g. a `ripemd160` block id, hex in JSON
+    Hash(usize),
+    /// Compact signature of 65 bytes, hex in JSON
+    Signature,
+    PublicKey,
+    TimePoint,
+    Version,
+    Asset,
+    Optional(&'static Type),
+    Vec(&'static Type),
+    /// `std::pair`, an array of two in JSON
+    Pair(&'static Type, &'static Type),
+    Struct(&'static [(&'static str, Type)]),
+    /// `static_variant`, `{"type": ..., "value": ...}` in JSON
+    Variant(&'static [(&'static str, Type)]),
+}
+
+/// Key consisting of zeroes, used e.g. to disable a witness
+pub(crate) const NULL_PUBLIC_KEY: &str = "STM1111111111111111111111111111111114T1Anm";
+
+/// Assets as NAI, precision and legacy symbol
+const ASSETS: &[(&str, u8, &[u8; 7])] = &[
+    ("@@000000021", 3, b"STEEM\0\0"),
+    ("@@000000013", 3, b"SBD\0\0\0\0"),
+    ("@@000000037", 6, b"VESTS\0\0"),
+];
+
+const EXTENSIONS: Type = Type::Vec(&Type::Variant(&[("void_t", Type::Struct(&[]))]));
+const ACCOUNTS: Type = Type::Vec(&Type::String);
+const PRICE: Type = Type::Struct(&[("base", Type::Asset), ("quote", Type::Asset)]);
+const AUTHORITY: Type = Type::Struct(&[
+    ("weight_threshold", Type::U32),
+    (
+        "account_auths",
+        Type::Vec(&Type::Pair(&Type::String, &Type::U16)),
+    ),
+    (
+        "key_auths",
+        Type::Vec(&Type::Pair(&Type::PublicKey, &Type::U16)),
+    ),
+]);
+const CHAIN_PROPERTIES: Type = Type::Struct(&[
+    ("account_creation_fee", Type::Asset),
+    ("maximum_block_size", Type::U32),
+    ("hbd_interest_rate", Type::U16),
+]);
+const POW2_INPUT: Type = Type::Struct(&[
+    ("worker_account", Type::String),
+    ("prev_block", Type::Hash(20)),
+    ("nonce", Type::U64),
+]);
+
+pub(crate) const BLOCK_HEADER_EXTENSIONS: Type = Type::Vec(&Type::Variant(&[
+    ("void_t", Type::Struct(&[])),
+    ("version", Type::Version),
+    (
+        "hardfork_version_vote",
+        Type::Struct(&[("hf_version", Type::Version), ("hf_time", Type::TimePoint)]),
+    ),
+]));
+
+const SIGNED_BLOCK_HEADER: Type = Type::Struct(&[
+    ("previous", Type::Hash(20)),
+    ("timestamp", Type::TimePoint),
+    ("witness", Type::String),
+    ("transaction_merkle_root", Type::Hash(20)),
+    ("extensions", BLOCK_HEADER_EXTENSIONS),
+    ("witness_signature", Type::Signature),
+]);
+
+/// Every operation in the order of hived's `operation` variant, named like `Operation`
+pub(crate) const OPERATION: Type = Type::Variant(&[
+    (
+        "vote_operation",
+        Type::Struct(&[
+            ("voter", Type::String),
+            ("author", Type::String),
+            ("permlink", Type::String),
+            ("weight", Type::I16),
+        ]),
+    ),
+    (
+        "comment_operation",
+        Type::Struct(&[
+            ("parent_author", Type::String),
+            ("parent_permlink", Type::String),
+            ("author", Type::String),
+            ("permlink", Type::String),
+            ("title", Type::String),
+            ("body", Type::String),
+            ("json_metadata", Type::String),
+        ]),
+    ),
+    (
+        "transfer_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("amount", Type::Asset),
+            ("memo", Type::String),
+        ]),
+    ),
+    (
+        "transfer_to_vesting_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("amount", Type::Asset),
+        ]),
+    ),
+    (
+        "withdraw_vesting_operation",
+        Type::Struct(&[("account", Type::String), ("vesting_shares", Type::Asset)]),
+    ),
+    (
+        "limit_order_create_operation",
+        Type::Struct(&[
+            ("owner", Type::String),
+            ("orderid", Type::U32),
+            ("amount_to_sell", Type::Asset),
+            ("min_to_receive", Type::Asset),
+            ("fill_or_kill", Type::Bool),
+            ("expiration", Type::TimePoint),
+        ]),
+    ),
+    (
+        "limit_order_cancel_operation",
+        Type::Struct(&[("owner", Type::String), ("orderid", Type::U32)]),
+    ),
+    (
+        "feed_publish_operation",
+        Type::Struct(&[("publisher", Type::String), ("exchange_rate", PRICE)]),
+    ),
+    (
+        "convert_operation",
+        Type::Struct(&[
+            ("owner", Type::String),
+            ("requestid", Type::U32),
+            ("amount", Type::Asset),
+        ]),
+    ),
+    (
+        "account_create_operation",
+        Type::Struct(&[
+            ("fee", Type::Asset),
+            ("creator", Type::String),
+            ("new_account_name", Type::String),
+            ("owner", AUTHORITY),
+            ("active", AUTHORITY),
+            ("posting", AUTHORITY),
+            ("memo_key", Type::PublicKey),
+            ("json_metadata", Type::String),
+        ]),
+    ),
+    (
+        "account_update_operation",
+        Type::Struct(&[
+            ("account", Type::String),
+            ("owner", Type::Optional(&AUTHORITY)),
+            ("active", Type::Optional(&AUTHORITY)),
+            ("posting", Type::Optional(&AUTHORITY)),
+            ("memo_key", Type::PublicKey),
+            ("json_metadata", Type::String),
+        ]),
+    ),
+    (
+        "witness_update_operation",
+        Type::Struct(&[
+            ("owner", Type::String),
+            ("url", Type::String),
+            ("block_signing_key", Type::PublicKey),
+            ("props", CHAIN_PROPERTIES),
+            ("fee", Type::Asset),
+        ]),
+    ),
+    (
+        "account_witness_vote_operation",
+        Type::Struct(&[
+            ("account", Type::String),
+            ("witness", Type::String),
+            ("approve", Type::Bool),
+        ]),
+    ),
+    (
+        "account_witness_proxy_operation",
+        Type::Struct(&[("account", Type::String), ("proxy", Type::String)]),
+    ),
+    (
+        "pow_operation",
+        Type::Struct(&[
+            ("worker_account", Type::String),
+            ("block_id", Type::Hash(20)),
+            ("nonce", Type::U64),
+            (
+                "work",
+                Type::Struct(&[
+                    ("worker", Type::PublicKey),
+                    ("input", Type::Hash(32)),
+                    ("signature", Type::Signature),
+                    ("work", Type::Hash(32)),
+                ]),
+            ),
+            ("props", CHAIN_PROPERTIES),
+        ]),
+    ),
+    (
+        "custom_operation",
+        Type::Struct(&[
+            ("required_auths", ACCOUNTS),
+            ("id", Type::U16),
+            ("data", Type::Bytes),
+        ]),
+    ),
+    (
+        "report_over_production_operation",
+        Type::Struct(&[
+            ("reporter", Type::String),
+            ("first_block", SIGNED_BLOCK_HEADER),
+            ("second_block", SIGNED_BLOCK_HEADER),
+        ]),
+    ),
+    (
+        "delete_comment_operation",
+        Type::Struct(&[("author", Type::String), ("permlink", Type::String)]),
+    ),
+    (
+        "custom_json_operation",
+        Type::Struct(&[
+            ("required_auths", ACCOUNTS),
+            ("required_posting_auths", ACCOUNTS),
+            ("id", Type::String),
+            ("json", Type::String),
+        ]),
+    ),
+    (
+        "comment_options_operation",
+        Type::Struct(&[
+            ("author", Type::String),
+            ("permlink", Type::String),
+            ("max_accepted_payout", Type::Asset),
+            ("percent_hbd", Type::U16),
+            ("allow_votes", Type::Bool),
+            ("allow_curation_rewards", Type::Bool),
+            (
+                "extensions",
+                Type::Vec(&Type::Variant(&[(
+                    "comment_payout_beneficiaries",
+                    Type::Struct(&[(
+                        "beneficiaries",
+                        Type::Vec(&Type::Struct(&[
+                            ("account", Type::String),
+                            ("weight", Type::U16),
+                        ])),
+                    )]),
+                )])),
+            ),
+        ]),
+    ),
+    (
+        "set_withdraw_vesting_route_operation",
+        Type::Struct(&[
+            ("from_account", Type::String),
+            ("to_account", Type::String),
+            ("percent", Type::U16),
+            ("auto_vest", Type::Bool),
+        ]),
+    ),
+    (
+        "limit_order_create2_operation",
+        Type::Struct(&[
+            ("owner", Type::String),
+            ("orderid", Type::U32),
+            ("amount_to_sell", Type::Asset),
+            ("exchange_rate", PRICE),
+            ("fill_or_kill", Type::Bool),
+            ("expiration", Type::TimePoint),
+        ]),
+    ),
+    (
+        "claim_account_operation",
+        Type::Struct(&[
+            ("creator", Type::String),
+            ("fee", Type::Asset),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "create_claimed_account_operation",
+        Type::Struct(&[
+            ("creator", Type::String),
+            ("new_account_name", Type::String),
+            ("owner", AUTHORITY),
+            ("active", AUTHORITY),
+            ("posting", AUTHORITY),
+            ("memo_key", Type::PublicKey),
+            ("json_metadata", Type::String),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "request_account_recovery_operation",
+        Type::Struct(&[
+            ("recovery_account", Type::String),
+            ("account_to_recover", Type::String),
+            ("new_owner_authority", AUTHORITY),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "recover_account_operation",
+        Type::Struct(&[
+            ("account_to_recover", Type::String),
+            ("new_owner_authority", AUTHORITY),
+            ("recent_owner_authority", AUTHORITY),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "change_recovery_account_operation",
+        Type::Struct(&[
+            ("account_to_recover", Type::String),
+            ("new_recovery_account", Type::String),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "escrow_transfer_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("hbd_amount", Type::Asset),
+            ("hive_amount", Type::Asset),
+            ("escrow_id", Type::U32),
+            ("agent", Type::String),
+            ("fee", Type::Asset),
+            ("json_meta", Type::String),
+            ("ratification_deadline", Type::TimePoint),
+            ("escrow_expiration", Type::TimePoint),
+        ]),
+    ),
+    (
+        "escrow_dispute_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("agent", Type::String),
+            ("who", Type::String),
+            ("escrow_id", Type::U32),
+        ]),
+    ),
+    (
+        "escrow_release_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("agent", Type::String),
+            ("who", Type::String),
+            ("receiver", Type::String),
+            ("escrow_id", Type::U32),
+            ("hbd_amount", Type::Asset),
+            ("hive_amount", Type::Asset),
+        ]),
+    ),
+    (
+        "pow2_operation",
+        Type::Struct(&[
+            (
+                "work",
+                Type::Variant(&[
+                    (
+                        "pow2",
+                        Type::Struct(&[("input", POW2_INPUT), ("pow_summary", Type::U32)]),
+                    ),
+                    (
+                        "equihash_pow",
+                        Type::Struct(&[
+                            ("input", POW2_INPUT),
+                            (
+                                "proof",
+                                Type::Struct(&[
+                                    ("n", Type::U32),
+                                    ("k", Type::U32),
+                                    ("seed", Type::Hash(32)),
+                                    ("inputs", Type::Vec(&Type::U32)),
+                                ]),
+                            ),
+                            ("prev_block", Type::Hash(20)),
+                            ("pow_summary", Type::U32),
+                        ]),
+                    ),
+                ]),
+            ),
+            ("new_owner_key", Type::Optional(&Type::PublicKey)),
+            ("props", CHAIN_PROPERTIES),
+        ]),
+    ),
+    (
+        "escrow_approve_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("agent", Type::String),
+            ("who", Type::String),
+            ("escrow_id", Type::U32),
+            ("approve", Type::Bool),
+        ]),
+    ),
+    (
+        "transfer_to_savings_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("amount", Type::Asset),
+            ("memo", Type::String),
+        ]),
+    ),
+    (
+        "transfer_from_savings_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("request_id", Type::U32),
+            ("to", Type::String),
+            ("amount", Type::Asset),
+            ("memo", Type::String),
+        ]),
+    ),
+    (
+        "cancel_transfer_from_savings_operation",
+        Type::Struct(&[("from", Type::String), ("request_id", Type::U32)]),
+    ),
+    (
+        "custom_binary_operation",
+        Type::Struct(&[
+            ("required_owner_auths", ACCOUNTS),
+            ("required_active_auths", ACCOUNTS),
+            ("required_posting_auths", ACCOUNTS),
+            ("required_auths", Type::Vec(&AUTHORITY)),
+            ("id", Type::String),
+            ("data", Type::Bytes),
+        ]),
+    ),
+    (
+        "decline_voting_rights_operation",
+        Type::Struct(&[("account", Type::String), ("decline", Type::Bool)]),
+    ),
+    (
+        "reset_account_operation",
+        Type::Struct(&[
+            ("reset_account", Type::String),
+            ("account_to_reset", Type::String),
+            ("new_owner_authority", AUTHORITY),
+        ]),
+    ),
+    (
+        "set_reset_account_operation",
+        Type::Struct(&[
+            ("account", Type::String),
+            ("current_reset_account", Type::String),
+            ("reset_account", Type::String),
+        ]),
+    ),
+    (
+        "claim_reward_balance_operation",
+        Type::Struct(&[
+            ("account", Type::String),
+            ("reward_hive", Type::Asset),
+            ("reward_hbd", Type::Asset),
+            ("reward_vests", Type::Asset),
+        ]),
+    ),
+    (
+        "delegate_vesting_shares_operation",
+        Type::Struct(&[
+            ("delegator", Type::String),
+            ("delegatee", Type::String),
+            ("vesting_shares", Type::Asset),
+        ]),
+    ),
+    (
+        "account_create_with_delegation_operation",
+        Type::Struct(&[
+            ("fee", Type::Asset),
+            ("delegation", Type::Asset),
+            ("creator", Type::String),
+            ("new_account_name", Type::String),
+            ("owner", AUTHORITY),
+            ("active", AUTHORITY),
+            ("posting", AUTHORITY),
+            ("memo_key", Type::PublicKey),
+            ("json_metadata", Type::String),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "witness_set_properties_operation",
+        Type::Struct(&[
+            ("owner", Type::String),
+            ("props", Type::Vec(&Type::Pair(&Type::String, &Type::Bytes))),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "account_update2_operation",
+        Type::Struct(&[
+            ("account", Type::String),
+            ("owner", Type::Optional(&AUTHORITY)),
+            ("active", Type::Optional(&AUTHORITY)),
+            ("posting", Type::Optional(&AUTHORITY)),
+            ("memo_key", Type::Optional(&Type::PublicKey)),
+            ("json_metadata", Type::String),
+            ("posting_json_metadata", Type::String),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "create_proposal_operation",
+        Type::Struct(&[
+            ("creator", Type::String),
+            ("receiver", Type::String),
+            ("start_date", Type::TimePoint),
+            ("end_date", Type::TimePoint),
+            ("daily_pay", Type::Asset),
+            ("subject", Type::String),
+            ("permlink", Type::String),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "update_proposal_votes_operation",
+        Type::Struct(&[
+            ("voter", Type::String),
+            ("proposal_ids", Type::Vec(&Type::I64)),
+            ("approve", Type::Bool),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "remove_proposal_operation",
+        Type::Struct(&[
+            ("proposal_owner", Type::String),
+            ("proposal_ids", Type::Vec(&Type::I64)),
+            ("extensions", EXTENSIONS),
+        ]),
+    ),
+    (
+        "update_proposal_operation",
+        Type::Struct(&[
+            ("proposal_id", Type::I64),
+            ("creator", Type::String),
+            ("daily_pay", Type::Asset),
+            ("subject", Type::String),
+            ("permlink", Type::String),
+            (
+                "extensions",
+                Type::Vec(&Type::Variant(&[
+                    ("void_t", Type::Struct(&[])),
+                    (
+                        "update_proposal_end_date",
+                        Type::Struct(&[("end_date", Type::TimePoint)]),
+                    ),
+                ])),
+            ),
+        ]),
+    ),
+    (
+        "collateralized_convert_operation",
+        Type::Struct(&[
+            ("owner", Type::String),
+            ("requestid", Type::U32),
+            ("amount", Type::Asset),
+        ]),
+    ),
+    (
+        "recurrent_transfer_operation",
+        Type::Struct(&[
+            ("from", Type::String),
+            ("to", Type::String),
+            ("amount", Type::Asset),
+            ("memo", Type::String),
+            ("recurrence", Type::U16),
+            ("executions", Type::U16),
+            (
+                "extensions",
+                Type::Vec(&Type::Variant(&[
+                    ("void_t", Type::Struct(&[])),
+                    (
+                        "recurrent_transfer_pair_id",
+                        Type::Struct(&[("pair_id", Type::U8)]),
+                    ),
+                ])),
+            ),
+        ]),
+    ),
+]);
+
+fn invalid(ty: &str, value: &Value) -> EncodeError {
+    EncodeError::InvalidValue(format!("expected {}, got {}", ty, value))
+}
+
+fn as_u64(value: &Value) -> Option<u64> {
+    match value {
+        Value::Number(n) => n.as_u64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn as_i64(value: &Value) -> Option<i64> {
+    match value {
+        Value::Number(n) => n.as_i64(),
+        Value::String(s) => s.parse().ok(),
+        _ => None,
+    }
+}
+
+fn hex_value(value: &Value, len: Option<usize>) -> Result<Vec<u8>, EncodeError> {
+    value
+        .as_str()
+        .and_then(|s| hex::decode(s).ok())
+        .filter(|bytes| len.unwrap_or(bytes.len()) == bytes.len())
+        .ok_or_else(|| invalid("hex", value))
+}
+
+/// Encodes `value` as the type `ty`
+///
+/// # Arguments
+/// * `value` - JSON as returned by `block_api`
+/// * `ty` - Type of the value
+/// * `encoder` - Encoder to write to
+pub(crate) fn encode_value<E: HiveEncoder>(
+    value: &Value,
+    ty: &Type,
+    encoder: &mut E,
+) -> Result<(), EncodeError> {
+    match ty {
+        Type::Bool => match value {
+            Value::Bool(b) => b.encode(encoder),
+            _ => Err(invalid("bool", value)),
+        },
+        Type::U8 => as_u64(value)
+            .and_then(|n| u8::try_from(n).ok())
+            .ok_or_else(|| invalid("u8", value))?
+            .encode(encoder),
+        Type::U16 => as_u64(value)
+            .and_then(|n| u16::try_from(n).ok())
+            .ok_or_else(|| invalid("u16", value))?
+            .encode(encoder),
+        Type::U32 => as_u64(value)
+            .and_then(|n| u32::try_from(n).ok())
+            .ok_or_else(|| invalid("u32", value))?
+            .encode(encoder),
+        Type::U64 => as_u64(value)
+            .ok_or_else(|| invalid("u64", value))?
+            .encode(encoder),
+        Type::I16 => as_i64(value)
+            .and_then(|n| i16::try_from(n).ok())
+            .ok_or_else(|| invalid("i16", value))?
+            .encode(encoder),
+        Type::I64 => as_i64(value)
+            .ok_or_else(|| invalid("i64", value))?
+            .encode(encoder),
+        Type::String => value
+            .as_str()
+            .ok_or_else(|| invalid("string", value))?
+            .encode(encoder),
+        Type::Bytes => hex_value(value, None)?.encode(encoder),
+        Type::Hash(len) => encoder.writer().write(&hex_value(value, Some(*len))?),
+        Type::Signature => encoder.writer().write(&hex_value(value, Some(65))?),
+        Type::PublicKey => match value.as_str() {
+            Some(NULL_PUBLIC_KEY) => encoder.writer().write(&[0; 33]),
+            Some(key) => PublicKey::from_wif(key)
+                .map_err(|_| invalid("public key", value))?
+                .encode(encoder),
+            None => Err(invalid("public key", value)),
+        },
+        Type::TimePoint => value
+            .as_str()
+            .and_then(|s| DateTime::<Utc>::from_str(&format!("{}Z", s)).ok())
+            .and_then(|time| u32::try_from(time.timestamp()).ok())
+            .ok_or_else(|| invalid("time", value))?
+            .encode(encoder),
+        Type::Version => value
+            .as_str()
+            .and_then(|s| s.parse::<BlockchainVersion>().ok())
+            .ok_or_else(|| invalid("version", value))?
+            .encode(encoder),
+        Type::Asset => {
+            let nai = value["nai"].as_str().unwrap_or_default();
+            let (_, precision, symbol) = ASSETS
+                .iter()
+                .find(|(n, _, _)| *n == nai)
+                .ok_or_else(|| invalid("asset", value))?;
+            let amount = as_i64(&value["amount"]).ok_or_else(|| invalid("asset", value))?;
+
+            amount.encode(encoder)?;
+            precision.encode(encoder)?;
+            encoder.writer().write(*symbol)
+        }
+        Type::Optional(ty) => match value {
+            Value::Null => false.encode(encoder),
+            value => {
+                true.encode(encoder)?;
+                encode_value(value, ty, encoder)
+            }
+        },
+        Type::Vec(ty) => {
+            let values = value.as_array().ok_or_else(|| invalid("array", value))?;
+
+            LEB128::from(values.len() as u64).encode(encoder)?;
+            for value in values {
+                encode_value(value, ty, encoder)?;
+            }
+
+            Ok(())
+        }
+        Type::Pair(first, second) => match value.as_array().map(Vec::as_slice) {
+            Some([a, b]) => {
+                encode_value(a, first, encoder)?;
+                encode_value(b, second, encoder)
+            }
+            _ => Err(invalid("pair", value)),
+        },
+        Type::Struct(fields) => {
+            if !value.is_object() {
+                return Err(invalid("object", value));
+            }
+
+            for (name, ty) in fields.iter() {
+                encode_value(&value[*name], ty, encoder)?;
+            }
+
+            Ok(())
+        }
+        Type::Variant(variants) => {
+            // Either `{"type": "name", "value": ...}` or the legacy `[index, ...]`
+            let (index, value) = match value {
+                Value::Object(object) => {
+                    let name = object.get("type").and_then(Value::as_str);
+                    let index = variants.iter().position(|(n, _)| Some(*n) == name);
+                    (index, object.get("value").unwrap_or(&Value::Null))
+                }
+                Value::Array(array) if array.len() == 2 => (
+                    array[0]
+                        .as_u64()
+                        .map(|i| i as usize)
+                        .filter(|i| *i < variants.len()),
+                    &array[1],
+                ),
+                _ => (None, &Value::Null),
+            };
+            let index = index.ok_or_else(|| invalid("variant", value))?;
+
+            LEB128::from(index as u64).encode(encoder)?;
+            encode_value(value, &variants[index].1, encoder)
+        }
+    }
+}
+
+/// Decodes a value of the type `ty` into JSON as returned by `block_api`
+///
+/// # Arguments
+/// * `ty` - Type of the value
+/// * `decoder` - Decoder to read from
+pub(crate) fn decode_value<D: HiveDecoder>(
+    ty: &Type,
+    decoder: &mut D,
+) -> Result<Value, DecodeError> {
+    let value = match ty {
+        Type::Bool => json!(bool::decode(decoder)?),
+        Type::U8 => json!(u8::decode(decoder)?),
+        Type::U16 => json!(u16::decode(decoder)?),
+        Type::U32 => json!(u32::decode(decoder)?),
+        Type::U64 => json!(u64::decode(decoder)?),
+        Type::I16 => json!(i16::decode(decoder)?),
+        Type::I64 => json!(i64::decode(decoder)?),
+        Type::String => json!(String::decode(decoder)?),
+        Type::Bytes => json!(hex::encode(Vec::<u8>::decode(decoder)?)),
+        Type::Hash(len) => {
+            let mut bytes = vec![0; *len];
+            decoder.reader().read(&mut bytes)?;
+            json!(hex::encode(bytes))
+        }
+        Type::Signature => json!(hex::encode(decode_without_size::<_, 65>(decoder)?)),
+        Type::PublicKey => {
+            let bytes: [u8; 33] = decode_without_size(decoder)?;
+
+            if bytes == [0; 33] {
+                json!(NULL_PUBLIC_KEY)
+            } else {
+                let key = secp256k1::PublicKey::from_slice(&bytes)
+                    .map_err(|e| DecodeError::InvalidValue(format!("public key {}", e)))?;
+                json!(PublicKey::from_key(key, None).to_wif())
+            }
+        }
+        Type::TimePoint => json!(DateTime::<Utc>::decode(decoder)?
+            .format("%Y-%m-%dT%H:%M:%S")
+            .to_string()),
+        Type::Version => json!(BlockchainVersion::decode(decoder)?.to_string()),
+        Type::Asset => {
+            let amount = i64::decode(decoder)?;
+            let precision = u8::decode(decoder)?;
+            let symbol: [u8; 7] = decode_without_size(decoder)?;
+            let (nai, _, _) = ASSETS
+                .iter()
+                .find(|(_, p, s)| *p == precision && **s == symbol)
+                .ok_or_else(|| {
+                    DecodeError::InvalidValue(format!("asset {}", String::from_utf8_lossy(&symbol)))
+                })?;
+
+            json!({ "amount": amount.to_string(), "precision": precision, "nai": nai })
+        }
+        Type::Optional(ty) => match bool::decode(decoder)? {
+            true => decode_value(ty, decoder)?,
+            false => Value::Null,
+        },
+        Type::Vec(ty) => {
+            let len = decode_leb128(decoder)?;
+            let mut values = vec![];
+
+            for _ in 0..len {
+                values.push(decode_value(ty, decoder)?);
+            }
+
+            Value::Array(values)
+        }
+        Type::Pair(first, second) => {
+            json!([
+                decode_value(first, decoder)?,
+                decode_value(second, decoder)?
+            ])
+        }
+        Type::Struct(fields) => {
+            let mut object = Map::new();
+
+            for (name, ty) in fields.iter() {
+                // Like hived, optional fields that are not set are left out
+                match decode_value(ty, decoder)? {
+                    Value::Null => {}
+                    value => {
+                        object.insert(name.to_string(), value);
+                    }
+                }
+            }
+
+            Value::Object(object)
+        }
+        Type::Variant(variants) => {
+            let index = decode_leb128(decoder)?;
+            let (name, ty) = variants
+                .get(index as usize)
+                .ok_or_else(|| DecodeError::InvalidValue(format!("variant {}", index)))?;
+
+            json!({ "type": name, "value": decode_value(ty, decoder)? })
+        }
+    };
+
+    Ok(value)
+}
+
+impl HiveEncode for Operation {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        let value =
+            serde_json::to_value(self).map_err(|e| EncodeError::InvalidValue(e.to_string()))?;
+
+        encode_value(&value, &OPERATION, encoder)
+    }
+}
+
+impl HiveDecode for Operation {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let value = decode_value(&OPERATION, decoder)?;
+
+        serde_json::from_value(value).map_err(|e| DecodeError::InvalidValue(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::encoding::{decode_value, encode_value, OPERATION};
+    use crate::block_api::operation::Operation;
+    use crate::dec::decode_from_slice;
+    use crate::enc::{encode_to_vec, EncodeError};
+    use crate::{HiveDecode, HiveDecoder, HiveEncode, HiveEncoder};
+    use serde_json::{json, Value};
+
+    fn encode(operation: serde_json::Value) -> String {
+        let operation: Operation = serde_json::from_value(operation).unwrap();
+
+        hex::encode(encode_to_vec(&operation).unwrap())
+    }
+
+    #[test]
+    fn encode_vote() {
+        let vote = json!({
+            "type": "vote_operation",
+            "value": { "voter": "alice", "author": "bob", "permlink": "post", "weight": -10000 }
+        });
+
+        assert_eq!(
+            encode(vote),
+            concat!("00", "05616c696365", "03626f62", "04706f7374", "f0d8")
+        );
+    }
+
+    #[test]
+    fn encode_transfer() {
+        let transfer = json!({
+            "type": "transfer_operation",
+            "value": {
+                "from": "alice",
+                "to": "bob",
+                "amount": { "amount": "1500", "precision": 3, "nai": "@@000000013" },
+                "memo": "hi"
+            }
+        });
+
+        assert_eq!(
+            encode(transfer),
+            concat!(
+                "02",
+                "05616c696365",
+                "03626f62",
+                "dc05000000000000",
+                "03",
+                "53424400000000",
+                "026869"
+            )
+        );
+    }
+
+    #[test]
+    fn encode_variants_and_optionals() {
+        let comment_options = json!({
+            "type": "comment_options_operation",
+            "value": {
+                "author": "bob",
+                "permlink": "post",
+                "max_accepted_payout": { "amount": "0", "precision": 3, "nai": "@@000000013" },
+                "percent_hbd": 10000,
+                "allow_votes": true,
+                "allow_curation_rewards": false,
+                "extensions": [{
+                    "type": "comment_payout_beneficiaries",
+                    "value": { "beneficiaries": [{ "account": "alice", "weight": 500 }] }
+                }]
+            }
+        });
+        let account_update2 = json!({
+            "type": "account_update2_operation",
+            "value": {
+                "account": "bob",
+                "json_metadata": "",
+                "posting_json_metadata": "{}",
+                "extensions": []
+            }
+        });
+
+        assert_eq!(
+            encode(comment_options),
+            concat!(
+                "13",
+                "03626f62",
+                "04706f7374",
+                "0000000000000000",
+                "03",
+                "53424400000000",
+                "1027",
+                "01",
+                "00",
+                "01",
+                "00",
+                "01",
+                "05616c696365",
+                "f401"
+            )
+        );
+        assert_eq!(
+            encode(account_update2),
+            concat!("2b", "03626f62", "00", "00", "00", "00", "00", "027b7d", "00")
+        );
+    }
+
+    #[test]
+    fn reject_malformed_values() {
+        let transfer = json!({
+            "type": "transfer_operation",
+            "value": {
+                "from": "alice",
+                "to": "bob",
+                "amount": { "amount": "1500", "precision": 3, "nai": "@@000000099" },
+                "memo": ""
+            }
+        });
+        let operation: Operation = serde_json::from_value(transfer).unwrap();
+
+        assert!(matches!(
+            encode_to_vec(&operation),
+            Err(EncodeError::InvalidValue(_))
+        ));
+    }
+
+    /// Operation as JSON, encoded through the schema
+    struct Json(Value);
+
+    impl HiveEncode for Json {
+        fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+            encode_value(&self.0, &OPERATION, encoder)
+        }
+    }
+
+    impl HiveDecode for Json {
+        fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, crate::dec::DecodeError> {
+            Ok(Json(decode_value(&OPERATION, decoder)?))
+        }
+    }
+
+    #[test]
+    fn decode_encoded_operations() {
+        let key = "STM6LLegbAgLAy28EHrffBVuANFWcFgmqRMW13wBmTExqFE9SCkg4";
+        let asset = json!({ "amount": "1000", "precision": 3, "nai": "@@000000021" });
+        let authority = json!({
+            "weight_threshold": 1,
+            "account_auths": [["alice", 1]],
+            "key_auths": [[key, 1]]
+        });
+        let operations = [
+            json!({
+                "type": "account_create_operation",
+                "value": {
+                    "fee": asset,
+                    "creator": "alice",
+                    "new_account_name": "bob",
+                    "owner": authority,
+                    "active": authority,
+                    "posting": authority,
+                    "memo_key": key,
+                    "json_metadata": ""
+                }
+            }),
+            json!({
+                "type": "account_update2_operation",
+                "value": {
+                    "account": "bob",
+                    "posting": authority,
+                    "memo_key": "STM1111111111111111111111111111111114T1Anm",
+                    "json_metadata": "",
+                    "posting_json_metadata": "{}",
+                    "extensions": []
+                }
+            }),
+            json!({
+                "type": "witness_set_properties_operation",
+                "value": {
+                    "owner": "alice",
+                    "props": [["key", "0102"], ["url", "0568747470"]],
+                    "extensions": []
+                }
+            }),
+            json!({
+                "type": "update_proposal_operation",
+                "value": {
+                    "proposal_id": 42,
+                    "creator": "alice",
+                    "daily_pay": { "amount": "5000000", "precision": 6, "nai": "@@000000037" },
+                    "subject": "subject",
+                    "permlink": "proposal",
+                    "extensions": [{
+                        "type": "update_proposal_end_date",
+                        "value": { "end_date": "2023-01-01T00:00:00" }
+                    }]
+                }
+            }),
+            json!({
+                "type": "pow2_operation",
+                "value": {
+                    "work": {
+                        "type": "pow2",
+                        "value": {
+                            "input": {
+                                "worker_account": "miner",
+                                "prev_block": "003ead0c90b0cd80e9145805d303957015c50ef1",
+                                "nonce": 10_000_000_000u64
+                            },
+                            "pow_summary": 3_817_748_868u32
+                        }
+                    },
+                    "props": {
+                        "account_creation_fee": asset,
+                        "maximum_block_size": 131_072,
+                        "hbd_interest_rate": 1000
+                    }
+                }
+            }),
+        ];
+
+        for operation in operations {
+            let bytes = encode_to_vec(Json(operation.clone())).unwrap();
+            let (decoded, len) = decode_from_slice::<Json>(&bytes).unwrap();
+
+            assert_eq!(len, bytes.len());
+            assert_eq!(decoded.0, operation);
+
+            let decoded: Operation = serde_json::from_value(decoded.0).unwrap();
+            assert_eq!(encode_to_vec(&decoded).unwrap(), bytes);
+        }
+    }
+}
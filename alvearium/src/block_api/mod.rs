@@ -1,16 +1,22 @@
 pub mod block;
+mod encoding;
 pub mod operation;
+mod operation_stream;
+mod stream;
 pub mod transaction;
 pub mod types;
+pub(crate) mod verify;
 
+use crate::client::{request, Batch, HiveClient};
 use block::{Block, BlockHeader, BlockHeaderResponse, BlockRangeResponse, BlockResponse};
-use jsonrpsee::core::client::ClientT;
 use jsonrpsee::core::traits::ToRpcParams;
 use jsonrpsee::core::Error;
-use jsonrpsee::http_client::HttpClient;
+pub use operation_stream::{stream_operations, BlockOperation, OperationFilter};
 use serde_json::json;
 use serde_json::value::RawValue;
 use std::ops::Range;
+pub use stream::{stream_block_events, stream_blocks, subscribe_new_blocks, BlockEvent};
+pub use verify::BlockVerifyError;
 
 struct BlockRange(Range<u32>);
 struct BlockNumber(u32);
@@ -35,29 +41,204 @@ impl ToRpcParams for BlockRange {
     }
 }
 
-pub async fn get_block(client: &HttpClient, n: u32) -> anyhow::Result<Block> {
-    let response: BlockResponse = client
-        .request("block_api.get_block", BlockNumber(n))
-        .await?;
+pub async fn get_block<C: HiveClient + ?Sized>(client: &C, n: u32) -> Result<Block, crate::Error> {
+    find_block(client, n)
+        .await?
+        .ok_or(crate::Error::BlockNotFound(n))
+}
+
+/// Most blocks `block_api.get_block_range` returns at once
+const MAX_BLOCK_RANGE: u32 = 1000;
+
+/// Most requests hived API nodes accept in one batch
+const MAX_BATCH_SIZE: usize = 50;
+
+/// Returns the given blocks in the same order, requested in JSON-RPC batches.
+///
+/// Needs far fewer round trips than calling `get_block` for every block.
+///
+/// # Arguments
+/// * `client` - Client to request the blocks with
+/// * `numbers` - Numbers of the blocks, all of them must exist
+pub async fn get_blocks<C: HiveClient + ?Sized>(
+    client: &C,
+    numbers: impl IntoIterator<Item = u32>,
+) -> Result<Vec<Block>, crate::Error> {
+    let numbers: Vec<u32> = numbers.into_iter().collect();
+    let mut blocks = Vec::with_capacity(numbers.len());
+
+    for chunk in numbers.chunks(MAX_BATCH_SIZE) {
+        let mut batch = Batch::new();
+        let items = chunk
+            .iter()
+            .map(|&n| batch.add::<BlockResponse, _>("block_api.get_block", BlockNumber(n)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let mut results = batch.send(client).await?;
+
+        for (item, &n) in items.into_iter().zip(chunk) {
+            let block = results
+                .take(item)?
+                .block
+                .ok_or(crate::Error::BlockNotFound(n))?;
+            blocks.push(block);
+        }
+    }
+
+    Ok(blocks)
+}
+
+/// Returns block `n`, or `None` if it was not produced yet
+pub(crate) async fn find_block<C: HiveClient + ?Sized>(
+    client: &C,
+    n: u32,
+) -> Result<Option<Block>, crate::Error> {
+    let response: BlockResponse = request(client, "block_api.get_block", BlockNumber(n)).await?;
 
     Ok(response.block)
 }
 
-pub async fn get_block_header(client: &HttpClient, n: u32) -> anyhow::Result<BlockHeader> {
-    let response: BlockHeaderResponse = client
-        .request("block_api.get_block_header", BlockNumber(n))
-        .await?;
+pub async fn get_block_header<C: HiveClient + ?Sized>(
+    client: &C,
+    n: u32,
+) -> Result<BlockHeader, crate::Error> {
+    let response: BlockHeaderResponse =
+        request(client, "block_api.get_block_header", BlockNumber(n)).await?;
 
     Ok(response.header)
 }
 
-pub async fn get_block_range(
-    client: &HttpClient,
+/// Returns the blocks in the range, requested in pages of the most blocks hived returns at once.
+///
+/// Stops early at the head block if the range reaches past it.
+///
+/// # Arguments
+/// * `client` - Client to request the blocks with
+/// * `r` - Numbers of the blocks
+pub async fn get_block_range<C: HiveClient + ?Sized>(
+    client: &C,
     r: Range<u32>,
-) -> anyhow::Result<Vec<BlockHeader>> {
-    let response: BlockRangeResponse = client
-        .request("block_api.get_block_range", BlockRange(r))
-        .await?;
+) -> Result<Vec<Block>, crate::Error> {
+    // The range can reach far past the head block, reserve at most one page up front
+    let mut blocks = Vec::with_capacity(r.len().min(MAX_BLOCK_RANGE as usize));
+    let mut start = r.start;
+
+    while start < r.end {
+        let end = r.end.min(start.saturating_add(MAX_BLOCK_RANGE));
+        let response: BlockRangeResponse =
+            request(client, "block_api.get_block_range", BlockRange(start..end)).await?;
+
+        let complete = response.blocks.len() == (end - start) as usize;
+        blocks.extend(response.blocks);
+        if !complete {
+            break;
+        }
+
+        start = end;
+    }
+
+    Ok(blocks)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::block_api::{get_block, get_block_range, get_blocks};
+    use crate::client::MockClient;
+    use crate::Error;
+    use serde_json::{json, Value};
 
-    Ok(response.blocks)
+    /// Id of block `n`, blocks on different forks get a different `branch`
+    pub(crate) fn block_id(n: u32, branch: u32) -> String {
+        format!("{:08x}{:032x}", n, branch)
+    }
+
+    /// Block `n` on `branch`, linked to block `n - 1` on `previous_branch`
+    pub(crate) fn block_json(n: u32, branch: u32, previous_branch: u32) -> Value {
+        json!({
+            "previous": block_id(n - 1, previous_branch),
+            "timestamp": "2022-10-20T10:00:00",
+            "witness": "initminer",
+            "transaction_merkle_root": "0000000000000000000000000000000000000000",
+            "extensions": [],
+            "witness_signature": "00",
+            "transactions": [],
+            "block_id": block_id(n, branch),
+            "signing_key": "STM6LLegbAgLAy28EHrffBVuANFWcFgmqRMW13wBmTExqFE9SCkg4",
+            "transaction_ids": []
+        })
+    }
+
+    #[tokio::test]
+    async fn get_block_range_in_pages() {
+        let client = MockClient::new();
+        client.respond_with("block_api.get_block_range", |params| {
+            let start = params["starting_block_num"].as_u64().unwrap() as u32;
+            let count = params["count"].as_u64().unwrap() as u32;
+            assert!(count <= 1000);
+
+            // The head block is 2400
+            let blocks: Vec<_> = (start..start + count)
+                .take_while(|&n| n <= 2400)
+                .map(|n| block_json(n, 0, 0))
+                .collect();
+            Ok(json!({ "blocks": blocks }))
+        });
+
+        let blocks = get_block_range(&client, 1..2501).await.unwrap();
+
+        assert_eq!(blocks.len(), 2400);
+        assert_eq!(blocks[1999].block_num(), Some(2000));
+        let pages: Vec<_> = client
+            .requests()
+            .into_iter()
+            .map(|(_, params)| {
+                (
+                    params["starting_block_num"].clone(),
+                    params["count"].clone(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            pages,
+            vec![
+                (json!(1), json!(1000)),
+                (json!(1001), json!(1000)),
+                (json!(2001), json!(500))
+            ]
+        );
+        assert!(get_block_range(&client, 5..5).await.unwrap().is_empty());
+        // Stops at the head block without reserving room for the whole range
+        let blocks = get_block_range(&client, 2301..u32::MAX).await.unwrap();
+        assert_eq!(blocks.len(), 100);
+    }
+
+    #[tokio::test]
+    async fn get_blocks_in_batches() {
+        let client = MockClient::new();
+        client.respond_with("block_api.get_block", |params| {
+            let n = params["block_num"].as_u64().unwrap() as u32;
+            Ok(json!({ "block": block_json(n, 0, 0) }))
+        });
+
+        let blocks = get_blocks(&client, 1..=120).await.unwrap();
+
+        let numbers: Vec<_> = blocks.iter().map(|b| b.block_num().unwrap()).collect();
+        assert_eq!(numbers, (1..=120).collect::<Vec<_>>());
+    }
+
+    #[tokio::test]
+    async fn get_block_not_found() {
+        let client = MockClient::new();
+        client.respond_with("block_api.get_block", |params| {
+            match params["block_num"].as_u64() {
+                Some(1) => Ok(json!({ "block": block_json(1, 0, 0) })),
+                _ => Ok(json!({})),
+            }
+        });
+
+        assert_eq!(get_block(&client, 1).await.unwrap().block_num(), Some(1));
+        assert!(matches!(
+            get_block(&client, 2).await,
+            Err(Error::BlockNotFound(2))
+        ));
+    }
 }
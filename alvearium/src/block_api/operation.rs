@@ -55,7 +55,7 @@ pub enum Operation {
     CreateClaimedAccount(Value),
     #[serde(rename = "request_account_recovery_operation")]
     RequestAccountRecovery(Value),
-    #[serde(rename = "recovery_account_operation")]
+    #[serde(rename = "recover_account_operation")]
     RecoverAccount(Value),
     #[serde(rename = "change_recovery_account_operation")]
     ChangeRecoveryAccount(Value),
@@ -106,3 +106,292 @@ pub enum Operation {
     #[serde(rename = "recurrent_transfer_operation")]
     RecurrentTransfer(Value),
 }
+
+/// Fields of operations that hold account names
+const ACCOUNT_FIELDS: &[&str] = &[
+    "account",
+    "account_to_recover",
+    "account_to_reset",
+    "agent",
+    "author",
+    "challenged",
+    "challenger",
+    "creator",
+    "current_owner",
+    "current_reset_account",
+    "delegatee",
+    "delegator",
+    "from",
+    "from_account",
+    "new_account_name",
+    "new_recovery_account",
+    "open_owner",
+    "owner",
+    "parent_author",
+    "producer",
+    "proposal_owner",
+    "proxy",
+    "publisher",
+    "receiver",
+    "recovery_account",
+    "reporter",
+    "required_active_auths",
+    "required_auths",
+    "required_owner_auths",
+    "required_posting_auths",
+    "reset_account",
+    "to",
+    "to_account",
+    "voter",
+    "who",
+    "witness",
+    "worker_account",
+];
+
+impl Operation {
+    /// Name of the operation as used by hived, e.g. `custom_json_operation`
+    pub fn name(&self) -> &'static str {
+        match self {
+            Operation::Vote(_) => "vote_operation",
+            Operation::Comment(_) => "comment_operation",
+            Operation::Transfer(_) => "transfer_operation",
+            Operation::TransferToVesting(_) => "transfer_to_vesting_operation",
+            Operation::WithdrawVesting(_) => "withdraw_vesting_operation",
+            Operation::LimitOrderCreate(_) => "limit_order_create_operation",
+            Operation::LimitOrderCancel(_) => "limit_order_cancel_operation",
+            Operation::FeedPublish(_) => "feed_publish_operation",
+            Operation::Convert(_) => "convert_operation",
+            Operation::AccountCreate(_) => "account_create_operation",
+            Operation::AccountUpdate(_) => "account_update_operation",
+            Operation::WitnessUpdate(_) => "witness_update_operation",
+            Operation::AccountWitnessVote(_) => "account_witness_vote_operation",
+            Operation::AccountWitnessProxy(_) => "account_witness_proxy_operation",
+            Operation::Pow(_) => "pow_operation",
+            Operation::Custom(_) => "custom_operation",
+            Operation::ReportOverProduction(_) => "report_over_production_operation",
+            Operation::DeleteComment(_) => "delete_comment_operation",
+            Operation::CustomJson(_) => "custom_json_operation",
+            Operation::CommentOptions(_) => "comment_options_operation",
+            Operation::SetWithdrawVestingRoute(_) => "set_withdraw_vesting_route_operation",
+            Operation::LimitOrderCreate2(_) => "limit_order_create2_operation",
+            Operation::ClaimAccount(_) => "claim_account_operation",
+            Operation::CreateClaimedAccount(_) => "create_claimed_account_operation",
+            Operation::RequestAccountRecovery(_) => "request_account_recovery_operation",
+            Operation::RecoverAccount(_) => "recover_account_operation",
+            Operation::ChangeRecoveryAccount(_) => "change_recovery_account_operation",
+            Operation::EscrowTransfer(_) => "escrow_transfer_operation",
+            Operation::EscrowDispute(_) => "escrow_dispute_operation",
+            Operation::EscrowRelease(_) => "escrow_release_operation",
+            Operation::Pow2(_) => "pow2_operation",
+            Operation::EscrowApprove(_) => "escrow_approve_operation",
+            Operation::TransferToSavings(_) => "transfer_to_savings_operation",
+            Operation::TransferFromSavings(_) => "transfer_from_savings_operation",
+            Operation::CancelTransferFromSavings(_) => "cancel_transfer_from_savings_operation",
+            Operation::CustomBinary(_) => "custom_binary_operation",
+            Operation::DeclineVotingRights(_) => "decline_voting_rights_operation",
+            Operation::ResetAccount(_) => "reset_account_operation",
+            Operation::SetResetAccount(_) => "set_reset_account_operation",
+            Operation::ClaimRewardBalance(_) => "claim_reward_balance_operation",
+            Operation::DelegateVestingShares(_) => "delegate_vesting_shares_operation",
+            Operation::AccountCreateWithDelegation(_) => "account_create_with_delegation_operation",
+            Operation::WitnessSetProperties(_) => "witness_set_properties_operation",
+            Operation::AccountUpdate2(_) => "account_update2_operation",
+            Operation::CreateProposal(_) => "create_proposal_operation",
+            Operation::UpdateProposalVotes(_) => "update_proposal_votes_operation",
+            Operation::RemoveProposal(_) => "remove_proposal_operation",
+            Operation::UpdateProposal(_) => "update_proposal_operation",
+            Operation::CollateralizedConvert(_) => "collateralized_convert_operation",
+            Operation::RecurrentTransfer(_) => "recurrent_transfer_operation",
+        }
+    }
+
+    /// Accounts the operation is about, e.g. the voter and author of a vote
+    pub fn accounts(&self) -> Vec<&str> {
+        match self {
+            Operation::Vote(vote) => vec![vote.voter.as_str(), vote.author.as_str()],
+            Operation::Custom(custom) => custom.required_auths.iter().map(String::as_str).collect(),
+            Operation::CustomJson(custom_json) => custom_json
+                .required_auths
+                .iter()
+                .chain(&custom_json.required_posting_auths)
+                .map(String::as_str)
+                .collect(),
+            _ => {
+                let mut accounts = vec![];
+
+                if let Some(Value::Object(fields)) = self.value() {
+                    for field in ACCOUNT_FIELDS {
+                        match fields.get(*field) {
+                            Some(Value::String(account)) => accounts.push(account.as_str()),
+                            Some(Value::Array(values)) => {
+                                accounts.extend(values.iter().filter_map(Value::as_str))
+                            }
+                            _ => {}
+                        }
+                    }
+                }
+
+                accounts.sort_unstable();
+                accounts.dedup();
+                accounts
+            }
+        }
+    }
+
+    /// Contents of the operations that are not parsed into a struct yet
+    fn value(&self) -> Option<&Value> {
+        match self {
+            Operation::Comment(value)
+            | Operation::Transfer(value)
+            | Operation::TransferToVesting(value)
+            | Operation::WithdrawVesting(value)
+            | Operation::LimitOrderCreate(value)
+            | Operation::LimitOrderCancel(value)
+            | Operation::FeedPublish(value)
+            | Operation::Convert(value)
+            | Operation::AccountCreate(value)
+            | Operation::AccountUpdate(value)
+            | Operation::WitnessUpdate(value)
+            | Operation::AccountWitnessVote(value)
+            | Operation::AccountWitnessProxy(value)
+            | Operation::Pow(value)
+            | Operation::ReportOverProduction(value)
+            | Operation::DeleteComment(value)
+            | Operation::CommentOptions(value)
+            | Operation::SetWithdrawVestingRoute(value)
+            | Operation::LimitOrderCreate2(value)
+            | Operation::ClaimAccount(value)
+            | Operation::CreateClaimedAccount(value)
+            | Operation::RequestAccountRecovery(value)
+            | Operation::RecoverAccount(value)
+            | Operation::ChangeRecoveryAccount(value)
+            | Operation::EscrowTransfer(value)
+            | Operation::EscrowDispute(value)
+            | Operation::EscrowRelease(value)
+            | Operation::Pow2(value)
+            | Operation::EscrowApprove(value)
+            | Operation::TransferToSavings(value)
+            | Operation::TransferFromSavings(value)
+            | Operation::CancelTransferFromSavings(value)
+            | Operation::CustomBinary(value)
+            | Operation::DeclineVotingRights(value)
+            | Operation::ResetAccount(value)
+            | Operation::SetResetAccount(value)
+            | Operation::ClaimRewardBalance(value)
+            | Operation::DelegateVestingShares(value)
+            | Operation::AccountCreateWithDelegation(value)
+            | Operation::WitnessSetProperties(value)
+            | Operation::AccountUpdate2(value)
+            | Operation::CreateProposal(value)
+            | Operation::UpdateProposalVotes(value)
+            | Operation::RemoveProposal(value)
+            | Operation::UpdateProposal(value)
+            | Operation::CollateralizedConvert(value)
+            | Operation::RecurrentTransfer(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::operation::Operation;
+    use serde_json::json;
+
+    fn accounts(name: &str, value: serde_json::Value) -> Vec<String> {
+        let operation: Operation =
+            serde_json::from_value(json!({ "type": name, "value": value })).unwrap();
+
+        operation
+            .accounts()
+            .into_iter()
+            .map(str::to_owned)
+            .collect()
+    }
+
+    #[test]
+    fn set_withdraw_vesting_route_accounts() {
+        let accounts = accounts(
+            "set_withdraw_vesting_route_operation",
+            json!({
+                "from_account": "alice",
+                "to_account": "bob",
+                "percent": 10000,
+                "auto_vest": false
+            }),
+        );
+
+        assert_eq!(accounts, vec!["alice", "bob"]);
+    }
+
+    #[test]
+    fn account_reset_accounts() {
+        let reset = accounts(
+            "reset_account_operation",
+            json!({
+                "reset_account": "alice",
+                "account_to_reset": "bob",
+                "new_owner_authority": { "weight_threshold": 1, "account_auths": [], "key_auths": [] }
+            }),
+        );
+        let set_reset = accounts(
+            "set_reset_account_operation",
+            json!({
+                "account": "bob",
+                "current_reset_account": "alice",
+                "reset_account": "carol"
+            }),
+        );
+
+        assert_eq!(reset, vec!["alice", "bob"]);
+        assert_eq!(set_reset, vec!["alice", "bob", "carol"]);
+    }
+
+    #[test]
+    fn remove_proposal_accounts() {
+        let accounts = accounts(
+            "remove_proposal_operation",
+            json!({ "proposal_owner": "alice", "proposal_ids": [1], "extensions": [] }),
+        );
+
+        assert_eq!(accounts, vec!["alice"]);
+    }
+
+    #[test]
+    fn witness_accounts() {
+        let report = accounts(
+            "report_over_production_operation",
+            json!({ "reporter": "alice", "first_block": {}, "second_block": {} }),
+        );
+        let pow = accounts(
+            "pow_operation",
+            json!({
+                "worker_account": "bob",
+                "block_id": "0000000000000000000000000000000000000000",
+                "nonce": 1,
+                "work": {},
+                "props": {}
+            }),
+        );
+
+        assert_eq!(report, vec!["alice"]);
+        assert_eq!(pow, vec!["bob"]);
+    }
+
+    #[test]
+    fn custom_binary_accounts() {
+        let accounts = accounts(
+            "custom_binary_operation",
+            json!({
+                "required_owner_auths": ["alice"],
+                "required_active_auths": ["bob"],
+                "required_posting_auths": ["carol"],
+                "required_auths": [{ "weight_threshold": 1, "account_auths": [], "key_auths": [] }],
+                "id": "test",
+                "data": ""
+            }),
+        );
+
+        assert_eq!(accounts, vec!["alice", "bob", "carol"]);
+    }
+}
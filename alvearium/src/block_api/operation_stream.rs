@@ -0,0 +1,338 @@
+use crate::block_api::block::Block;
+use crate::block_api::operation::Operation;
+use crate::block_api::stream::stream_blocks;
+use crate::block_api::verify::BlockVerifyError;
+use crate::client::HiveClient;
+use crate::condenser_api::transaction::BlockchainMode;
+use crate::Error;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, Stream, StreamExt};
+
+/// Operation of a transaction together with where and when it was included
+#[derive(Debug)]
+pub struct BlockOperation {
+    pub block_num: u32,
+    pub trx_id: String,
+    /// Index of the transaction in the block
+    pub trx_in_block: u32,
+    /// Index of the operation in the transaction
+    pub op_in_trx: u32,
+    pub timestamp: DateTime<Utc>,
+    pub operation: Operation,
+}
+
+/// Selects operations by type, account or custom_json id.
+///
+/// An operation matches if it matches every criterion that was set, and a criterion matches if
+/// any of its values match. An empty filter matches every operation.
+#[derive(Debug, Clone, Default)]
+pub struct OperationFilter {
+    names: Vec<String>,
+    accounts: Vec<String>,
+    custom_json_ids: Vec<String>,
+}
+
+impl OperationFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Matches operations with this name, e.g. `vote_operation` or `vote`
+    pub fn operation(mut self, name: impl Into<String>) -> Self {
+        let mut name = name.into();
+        if !name.ends_with("_operation") {
+            name.push_str("_operation");
+        }
+
+        self.names.push(name);
+        self
+    }
+
+    /// Matches operations involving this account, see `Operation::accounts`
+    pub fn account(mut self, account: impl Into<String>) -> Self {
+        self.accounts.push(account.into());
+        self
+    }
+
+    /// Matches custom_json operations with this id, other operations do not match anymore
+    pub fn custom_json_id(mut self, id: impl Into<String>) -> Self {
+        self.custom_json_ids.push(id.into());
+        self
+    }
+
+    pub fn matches(&self, operation: &Operation) -> bool {
+        let name = self.names.is_empty() || self.names.iter().any(|n| n == operation.name());
+        let account = self.accounts.is_empty()
+            || operation
+                .accounts()
+                .iter()
+                .any(|a| self.accounts.iter().any(|account| account == a));
+        let custom_json_id = self.custom_json_ids.is_empty()
+            || matches!(operation, Operation::CustomJson(custom_json)
+                if self.custom_json_ids.contains(&custom_json.id));
+
+        name && account && custom_json_id
+    }
+}
+
+impl Block {
+    /// Splits the block into its operations, in the order they were applied
+    pub fn into_operations(self) -> Result<Vec<BlockOperation>, Error> {
+        let block_num = self
+            .block_num()
+            .ok_or_else(|| Error::InvalidBlockId(self.block_id.clone()))?;
+        if self.transaction_ids.len() != self.transactions.len() {
+            return Err(Error::InvalidBlock(
+                block_num,
+                BlockVerifyError::TransactionCountMismatch,
+            ));
+        }
+
+        let timestamp = self.timestamp;
+        let mut operations = vec![];
+
+        for (trx_in_block, (transaction, trx_id)) in self
+            .transactions
+            .into_iter()
+            .zip(self.transaction_ids)
+            .enumerate()
+        {
+            for (op_in_trx, operation) in transaction.operations.into_iter().enumerate() {
+                operations.push(BlockOperation {
+                    block_num,
+                    trx_id: trx_id.clone(),
+                    trx_in_block: trx_in_block as u32,
+                    op_in_trx: op_in_trx as u32,
+                    timestamp,
+                    operation,
+                });
+            }
+        }
+
+        Ok(operations)
+    }
+}
+
+/// Streams the operations matching `filter` of every block starting at `from`.
+///
+/// Blocks are streamed like `stream_blocks`, errors are yielded the same way.
+///
+/// # Arguments
+/// * `client` - Client to request the blocks with
+/// * `from` - Number of the first block
+/// * `mode` - `Reversible` to follow the head block, `Irreversible` for the last irreversible block
+/// * `filter` - Operations to yield, `OperationFilter::new()` for all of them
+pub fn stream_operations<C: HiveClient>(
+    client: C,
+    from: u32,
+    mode: BlockchainMode,
+    filter: OperationFilter,
+) -> impl Stream<Item = Result<BlockOperation, Error>> {
+    operations(stream_blocks(client, from, mode), filter)
+}
+
+pub(crate) fn operations(
+    blocks: impl Stream<Item = Result<Block, Error>>,
+    filter: OperationFilter,
+) -> impl Stream<Item = Result<BlockOperation, Error>> {
+    blocks.flat_map(move |block| {
+        let operations = match block.and_then(Block::into_operations) {
+            Ok(operations) => operations
+                .into_iter()
+                .filter(|operation| filter.matches(&operation.operation))
+                .map(Ok)
+                .collect(),
+            Err(e) => vec![Err(e)],
+        };
+
+        stream::iter(operations)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::block::Block;
+    use crate::block_api::operation_stream::{operations, OperationFilter};
+    use crate::block_api::tests::block_json;
+    use crate::block_api::BlockVerifyError;
+    use crate::Error;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+
+    fn block(n: u32, transactions: Vec<Vec<Value>>) -> Block {
+        let mut block = block_json(n, 0, 0);
+        block["transaction_ids"] = json!((0..transactions.len())
+            .map(|i| format!("{:040x}", n as usize * 100 + i))
+            .collect::<Vec<_>>());
+        block["transactions"] = json!(transactions
+            .into_iter()
+            .map(|operations| json!({
+                "ref_block_num": 1,
+                "ref_block_prefix": 2,
+                "expiration": "2022-10-20T10:10:00",
+                "operations": operations,
+                "signatures": [],
+                "extensions": []
+            }))
+            .collect::<Vec<_>>());
+
+        serde_json::from_value(block).unwrap()
+    }
+
+    fn vote(voter: &str, author: &str) -> Value {
+        json!({
+            "type": "vote_operation",
+            "value": { "voter": voter, "author": author, "permlink": "post", "weight": 10000 }
+        })
+    }
+
+    fn transfer(from: &str, to: &str) -> Value {
+        json!({
+            "type": "transfer_operation",
+            "value": {
+                "from": from,
+                "to": to,
+                "amount": { "amount": "1000", "precision": 3, "nai": "@@000000021" },
+                "memo": ""
+            }
+        })
+    }
+
+    fn custom_json(account: &str, id: &str) -> Value {
+        json!({
+            "type": "custom_json_operation",
+            "value": {
+                "required_auths": [],
+                "required_posting_auths": [account],
+                "id": id,
+                "json": "{}"
+            }
+        })
+    }
+
+    fn recover_account(account: &str) -> Value {
+        let authority = json!({
+            "weight_threshold": 1,
+            "account_auths": [],
+            "key_auths": [["STM6LLegbAgLAy28EHrffBVuANFWcFgmqRMW13wBmTExqFE9SCkg4", 1]]
+        });
+
+        json!({
+            "type": "recover_account_operation",
+            "value": {
+                "account_to_recover": account,
+                "new_owner_authority": authority,
+                "recent_owner_authority": authority,
+                "extensions": []
+            }
+        })
+    }
+
+    fn blocks() -> Vec<Block> {
+        vec![
+            block(
+                10,
+                vec![
+                    vec![vote("alice", "bob"), custom_json("alice", "follow")],
+                    vec![transfer("carol", "dave")],
+                ],
+            ),
+            block(11, vec![]),
+            block(12, vec![vec![custom_json("bob", "notify")]]),
+        ]
+    }
+
+    async fn filtered(filter: OperationFilter) -> Vec<(u32, u32, u32, &'static str)> {
+        operations(futures::stream::iter(blocks().into_iter().map(Ok)), filter)
+            .map(|operation| {
+                let operation = operation.unwrap();
+                (
+                    operation.block_num,
+                    operation.trx_in_block,
+                    operation.op_in_trx,
+                    operation.operation.name(),
+                )
+            })
+            .collect()
+            .await
+    }
+
+    #[test]
+    fn into_operations() {
+        let operations = blocks().remove(0).into_operations().unwrap();
+
+        assert_eq!(operations.len(), 3);
+        assert_eq!(operations[1].trx_id, format!("{:040x}", 1000));
+        assert_eq!(operations[2].trx_id, format!("{:040x}", 1001));
+        assert_eq!(operations[2].trx_in_block, 1);
+        assert_eq!(operations[2].operation.accounts(), vec!["carol", "dave"]);
+    }
+
+    #[test]
+    fn missing_transaction_ids() {
+        let mut block = blocks().remove(0);
+        block.transaction_ids.pop();
+
+        assert!(matches!(
+            block.into_operations(),
+            Err(Error::InvalidBlock(
+                10,
+                BlockVerifyError::TransactionCountMismatch
+            ))
+        ));
+    }
+
+    #[tokio::test]
+    async fn recover_account_operations() {
+        let blocks = vec![block(13, vec![vec![recover_account("alice")]])];
+        let filter = OperationFilter::new()
+            .operation("recover_account")
+            .account("alice");
+
+        let operations: Vec<_> =
+            operations(futures::stream::iter(blocks.into_iter().map(Ok)), filter)
+                .collect()
+                .await;
+
+        assert_eq!(operations.len(), 1);
+        let operation = operations[0].as_ref().unwrap();
+        assert_eq!(operation.block_num, 13);
+        assert_eq!(operation.operation.name(), "recover_account_operation");
+        assert_eq!(operation.operation.accounts(), vec!["alice"]);
+    }
+
+    #[tokio::test]
+    async fn filter_operations() {
+        assert_eq!(filtered(OperationFilter::new()).await.len(), 4);
+        assert_eq!(
+            filtered(OperationFilter::new().operation("vote")).await,
+            vec![(10, 0, 0, "vote_operation")]
+        );
+        assert_eq!(
+            filtered(OperationFilter::new().account("alice")).await,
+            vec![
+                (10, 0, 0, "vote_operation"),
+                (10, 0, 1, "custom_json_operation")
+            ]
+        );
+        assert_eq!(
+            filtered(
+                OperationFilter::new()
+                    .custom_json_id("follow")
+                    .custom_json_id("notify")
+                    .account("bob")
+            )
+            .await,
+            vec![(12, 0, 0, "custom_json_operation")]
+        );
+        assert_eq!(
+            filtered(
+                OperationFilter::new()
+                    .operation("transfer_operation")
+                    .account("dave")
+            )
+            .await,
+            vec![(10, 1, 0, "transfer_operation")]
+        );
+    }
+}
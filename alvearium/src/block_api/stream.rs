@@ -0,0 +1,589 @@
+use crate::block_api::block::Block;
+use crate::block_api::{get_block, get_blocks};
+use crate::client::HiveClient;
+use crate::condenser_api::transaction::BlockchainMode;
+use crate::database_api::get_dynamic_global_properties;
+use crate::Error;
+use futures::stream::{self, Stream, StreamExt};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Time between two blocks
+const BLOCK_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Most blocks requested at once while catching up
+const CATCH_UP_BATCH_SIZE: u32 = 50;
+
+/// Most delivered blocks remembered to find where a fork started
+const MAX_FORK_DEPTH: usize = 1000;
+
+/// Event delivered by `stream_block_events`
+#[allow(clippy::large_enum_variant)]
+#[derive(Debug)]
+pub enum BlockEvent {
+    /// The next block
+    Block(Block),
+    /// The blocks after `to_block` were replaced by a fork, undo them. The blocks of the new
+    /// branch follow.
+    Rollback { to_block: u32 },
+}
+
+struct BlockStream<C> {
+    client: C,
+    mode: BlockchainMode,
+    next: u32,
+    /// Whether `next` is set to the block after the head at the first check
+    start_at_head: bool,
+    /// Last block that can be delivered in `mode`, as of the last check
+    last: u32,
+    /// Last irreversible block, as of the last check
+    irreversible: u32,
+    buffer: VecDeque<Block>,
+    /// Number and id of the delivered reversible blocks, to check the links of the next blocks
+    delivered: VecDeque<(u32, String)>,
+    failed: bool,
+}
+
+/// Streams every block starting at `from`, following the head block or the last irreversible
+/// block.
+///
+/// Blocks behind the followed block are requested in batches until the stream caught up, after
+/// that it waits a block interval between checks for new blocks. To resume after a restart,
+/// store the number of the last processed block and pass the following number as `from`.
+/// Errors are yielded and the same blocks are requested again after a block interval, drop the
+/// stream to stop. Block 1 is the first block of the chain, a `from` of 0 starts there.
+///
+/// When following the head block, blocks of a fork are delivered again after the fork was
+/// resolved. Use `stream_block_events` to know which blocks were replaced.
+///
+/// # Arguments
+/// * `client` - Client to request the blocks with
+/// * `from` - Number of the first block
+/// * `mode` - `Reversible` to follow the head block, `Irreversible` to only deliver blocks that
+///   can not be undone anymore
+pub fn stream_blocks<C: HiveClient>(
+    client: C,
+    from: u32,
+    mode: BlockchainMode,
+) -> impl Stream<Item = Result<Block, Error>> {
+    blocks(stream_block_events(client, from, mode))
+}
+
+fn blocks(
+    events: impl Stream<Item = Result<BlockEvent, Error>>,
+) -> impl Stream<Item = Result<Block, Error>> {
+    events.filter_map(|event| async move {
+        match event {
+            Ok(BlockEvent::Block(block)) => Some(Ok(block)),
+            Ok(BlockEvent::Rollback { .. }) => None,
+            Err(e) => Some(Err(e)),
+        }
+    })
+}
+
+/// Follows the head of the chain and yields every new block, starting after the current head.
+///
+/// Works like `stream_blocks` in `Reversible` mode and checks for a new block once a block
+/// interval. Over a `WsClient` these requests share one connection instead of opening an HTTP
+/// request each. To start at a given block or to follow irreversible blocks, use `stream_blocks`.
+///
+/// # Arguments
+/// * `client` - Client to follow the chain with, preferably a `WsClient`
+pub fn subscribe_new_blocks<C: HiveClient>(client: C) -> impl Stream<Item = Result<Block, Error>> {
+    blocks(block_events(
+        client,
+        None,
+        BlockchainMode::Reversible,
+        BLOCK_INTERVAL,
+    ))
+}
+
+/// Streams blocks like `stream_blocks`, and reports forks when following the head block.
+///
+/// Every block is checked to link to the previously delivered block. If it does not, the stream
+/// looks for the last block both branches share, yields a `Rollback` to it and delivers the
+/// blocks of the new branch.
+///
+/// # Arguments
+/// * `client` - Client to request the blocks with
+/// * `from` - Number of the first block
+/// * `mode` - `Reversible` to follow the head block, `Irreversible` to only deliver blocks that
+///   can not be undone anymore
+pub fn stream_block_events<C: HiveClient>(
+    client: C,
+    from: u32,
+    mode: BlockchainMode,
+) -> impl Stream<Item = Result<BlockEvent, Error>> {
+    stream_block_events_with_interval(client, from, mode, BLOCK_INTERVAL)
+}
+
+pub(crate) fn stream_block_events_with_interval<C: HiveClient>(
+    client: C,
+    from: u32,
+    mode: BlockchainMode,
+    interval: Duration,
+) -> impl Stream<Item = Result<BlockEvent, Error>> {
+    block_events(client, Some(from), mode, interval)
+}
+
+/// Stream of the blocks from `from`, or after the head block if `None`
+fn block_events<C: HiveClient>(
+    client: C,
+    from: Option<u32>,
+    mode: BlockchainMode,
+    interval: Duration,
+) -> impl Stream<Item = Result<BlockEvent, Error>> {
+    let state = BlockStream {
+        client,
+        mode,
+        // There is no block 0, `get_block` would never find it
+        next: from.unwrap_or_default().max(1),
+        start_at_head: from.is_none(),
+        last: 0,
+        irreversible: 0,
+        buffer: VecDeque::new(),
+        delivered: VecDeque::new(),
+        failed: false,
+    };
+
+    stream::unfold(state, move |mut state| async move {
+        if state.failed {
+            tokio::time::sleep(interval).await;
+            state.failed = false;
+        }
+
+        loop {
+            let result = match state.buffer.pop_front() {
+                Some(block) => state.deliver(block).await,
+                None => state.fill_buffer(interval).await.map(|_| None),
+            };
+
+            match result {
+                Ok(Some(event)) => return Some((Ok(event), state)),
+                Ok(None) => {}
+                Err(e) => {
+                    state.failed = true;
+                    return Some((Err(e), state));
+                }
+            }
+        }
+    })
+}
+
+impl<C: HiveClient> BlockStream<C> {
+    /// Requests the next batch of blocks, waiting until there is at least one
+    async fn fill_buffer(&mut self, interval: Duration) -> Result<(), Error> {
+        while self.start_at_head || self.next > self.last {
+            if self.last != 0 {
+                tokio::time::sleep(interval).await;
+            }
+
+            let properties = get_dynamic_global_properties(&self.client).await?;
+            self.irreversible = properties.last_irreversible_block_num;
+            self.last = match self.mode {
+                BlockchainMode::Reversible => properties.head_block_number,
+                BlockchainMode::Irreversible => properties.last_irreversible_block_num,
+            };
+
+            if self.start_at_head {
+                self.next = self.last + 1;
+                self.start_at_head = false;
+            }
+        }
+
+        let end = self.last.min(self.next + CATCH_UP_BATCH_SIZE - 1);
+        let blocks = get_blocks(&self.client, self.next..=end).await?;
+
+        self.next = end + 1;
+        self.buffer.extend(blocks);
+
+        Ok(())
+    }
+
+    /// Returns the event for the next block, after checking that it links to the delivered
+    /// blocks. On failure the block is put back to try again.
+    async fn deliver(&mut self, block: Block) -> Result<Option<BlockEvent>, Error> {
+        let n = block
+            .block_num()
+            .ok_or_else(|| Error::InvalidBlockId(block.block_id.clone()))?;
+
+        if self.mode == BlockchainMode::Irreversible {
+            return Ok(Some(BlockEvent::Block(block)));
+        }
+
+        match self.delivered.back() {
+            Some((last, id)) if *last + 1 == n && *id != block.previous => {
+                Ok(Some(self.find_fork(block).await?))
+            }
+            _ => {
+                self.delivered.push_back((n, block.block_id.clone()));
+                // Forks can not go past the last irreversible block, the last delivered block is
+                // kept to check the link of the next one
+                while self.delivered.len() > MAX_FORK_DEPTH
+                    || self.delivered.len() > 1
+                        && matches!(self.delivered.front(), Some((n, _)) if *n < self.irreversible)
+                {
+                    self.delivered.pop_front();
+                }
+
+                Ok(Some(BlockEvent::Block(block)))
+            }
+        }
+    }
+
+    /// Queues the blocks of the new branch `block` belongs to and returns the rollback to the
+    /// last block both branches share
+    async fn find_fork(&mut self, block: Block) -> Result<BlockEvent, Error> {
+        let mut branch = vec![block];
+
+        match self.walk_back(&mut branch).await {
+            Ok(to_block) => {
+                self.delivered.retain(|(n, _)| *n <= to_block);
+                for block in branch {
+                    self.buffer.push_front(block);
+                }
+
+                Ok(BlockEvent::Rollback { to_block })
+            }
+            Err(e) => {
+                self.buffer.push_front(branch.swap_remove(0));
+                Err(e)
+            }
+        }
+    }
+
+    /// Requests the previous blocks of the new branch until one links to a delivered block, or
+    /// until the last irreversible block when the fork started before the remembered blocks
+    async fn walk_back(&self, branch: &mut Vec<Block>) -> Result<u32, Error> {
+        loop {
+            let oldest = &branch[branch.len() - 1];
+            let n = oldest
+                .block_num()
+                .ok_or_else(|| Error::InvalidBlockId(oldest.block_id.clone()))?;
+
+            match self.delivered.iter().find(|(number, _)| *number + 1 == n) {
+                Some((_, id)) if *id == oldest.previous => return Ok(n - 1),
+                // Irreversible blocks are on every branch
+                None if n <= self.irreversible + 1 => return Ok(n.saturating_sub(1)),
+                _ => branch.push(get_block(&self.client, n - 1).await?),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::block::Block;
+    use crate::block_api::stream::{
+        block_events, blocks, stream_block_events_with_interval, BlockEvent, BlockStream,
+    };
+    use crate::block_api::tests::block_id;
+    use crate::block_api::tests::block_json;
+    use crate::client::{HiveClient, MockClient};
+    use crate::condenser_api::transaction::BlockchainMode;
+    use crate::database_api::tests::dynamic_global_properties_json;
+    use crate::Error;
+    use async_trait::async_trait;
+    use chrono::Utc;
+    use futures::{Stream, StreamExt};
+    use serde_json::value::RawValue;
+    use serde_json::{json, Value};
+    use std::collections::VecDeque;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn stream_blocks_with_interval(
+        client: &MockClient,
+        from: u32,
+        mode: BlockchainMode,
+    ) -> impl Stream<Item = Result<Block, Error>> + '_ {
+        blocks(stream_block_events_with_interval(
+            client,
+            from,
+            mode,
+            Duration::ZERO,
+        ))
+    }
+
+    /// Counts the batches sent through the mock, which answers batched requests one by one
+    struct BatchCounter {
+        client: MockClient,
+        batches: AtomicU32,
+    }
+
+    #[async_trait]
+    impl HiveClient for BatchCounter {
+        async fn request_raw(
+            &self,
+            method: &str,
+            params: Option<Box<RawValue>>,
+        ) -> Result<Value, Error> {
+            self.client.request_raw(method, params).await
+        }
+
+        async fn batch_raw(
+            &self,
+            requests: Vec<(String, Option<Box<RawValue>>)>,
+        ) -> Result<Vec<Result<Value, Error>>, Error> {
+            self.batches.fetch_add(1, Ordering::SeqCst);
+            self.client.batch_raw(requests).await
+        }
+    }
+
+    /// Node whose head block advances by one on every `get_dynamic_global_properties`
+    fn chain(head: u32, irreversible_behind: u32) -> MockClient {
+        let client = MockClient::new();
+        let head = Arc::new(AtomicU32::new(head));
+
+        let block_head = head.clone();
+        client.respond_with("block_api.get_block", move |params| {
+            let n = params["block_num"].as_u64().unwrap() as u32;
+
+            match n <= block_head.load(Ordering::SeqCst) {
+                true => Ok(json!({ "block": block_json(n, 0, 0) })),
+                false => Ok(json!({})),
+            }
+        });
+        client.respond_with("database_api.get_dynamic_global_properties", move |_| {
+            let head = head.fetch_add(1, Ordering::SeqCst);
+            Ok(dynamic_global_properties_json(
+                head,
+                head - irreversible_behind,
+                Utc::now(),
+            ))
+        });
+
+        client
+    }
+
+    #[tokio::test]
+    async fn catches_up_and_follows_head() {
+        let client = BatchCounter {
+            client: chain(100, 20),
+            batches: AtomicU32::new(0),
+        };
+
+        let blocks: Vec<_> = blocks(stream_block_events_with_interval(
+            &client,
+            1,
+            BlockchainMode::Reversible,
+            Duration::ZERO,
+        ))
+        .take(103)
+        .map(|block| block.unwrap().block_num().unwrap())
+        .collect()
+        .await;
+
+        assert_eq!(blocks, (1..=103).collect::<Vec<_>>());
+        // 100 blocks in 2 batches of 50 and a batch of one per new block
+        assert_eq!(client.batches.load(Ordering::SeqCst), 5);
+        let block_requests = client
+            .client
+            .requests()
+            .iter()
+            .filter(|(method, _)| method == "block_api.get_block")
+            .count();
+        assert_eq!(block_requests, 103);
+        // One check before catching up and one per new block
+        let property_requests = client.client.requests().len() - block_requests;
+        assert_eq!(property_requests, 4);
+    }
+
+    #[tokio::test]
+    async fn subscribes_after_head() {
+        let client = chain(100, 20);
+
+        let blocks: Vec<_> = blocks(block_events(
+            &client,
+            None,
+            BlockchainMode::Reversible,
+            Duration::ZERO,
+        ))
+        .take(2)
+        .map(|block| block.unwrap().block_num().unwrap())
+        .collect()
+        .await;
+
+        assert_eq!(blocks, vec![101, 102]);
+    }
+
+    #[tokio::test]
+    async fn block_0_starts_at_block_1() {
+        let client = chain(10, 0);
+
+        let block = Box::pin(stream_blocks_with_interval(
+            &client,
+            0,
+            BlockchainMode::Irreversible,
+        ))
+        .next()
+        .await
+        .unwrap();
+
+        assert_eq!(block.unwrap().block_num(), Some(1));
+    }
+
+    #[tokio::test]
+    async fn follows_irreversible_blocks() {
+        let client = chain(100, 20);
+
+        let blocks: Vec<_> = stream_blocks_with_interval(&client, 79, BlockchainMode::Irreversible)
+            .take(3)
+            .map(|block| block.unwrap().block_num().unwrap())
+            .collect()
+            .await;
+
+        assert_eq!(blocks, vec![79, 80, 81]);
+        // Block 81 only became irreversible with the second head block
+        let requested: Vec<_> = client
+            .requests()
+            .into_iter()
+            .filter(|(method, _)| method == "block_api.get_block")
+            .map(|(_, params)| params["block_num"].as_u64().unwrap())
+            .collect();
+        assert_eq!(requested, vec![79, 80, 81]);
+    }
+
+    #[tokio::test]
+    async fn resumes_after_errors() {
+        let client = chain(10, 0);
+        let failures = AtomicU32::new(0);
+        client.respond_with("block_api.get_block", move |params| {
+            if failures.fetch_add(1, Ordering::SeqCst) == 0 {
+                return Err(crate::RpcError {
+                    code: -32003,
+                    message: "Unable to acquire database lock".to_owned(),
+                    data: None,
+                });
+            }
+
+            let n = params["block_num"].as_u64().unwrap() as u32;
+            Ok(json!({ "block": block_json(n, 0, 0) }))
+        });
+
+        let blocks: Vec<_> = stream_blocks_with_interval(&client, 5, BlockchainMode::Reversible)
+            .take(3)
+            .collect()
+            .await;
+
+        assert!(blocks[0].is_err());
+        assert_eq!(blocks[1].as_ref().unwrap().block_num(), Some(5));
+        assert_eq!(blocks[2].as_ref().unwrap().block_num(), Some(6));
+    }
+
+    #[tokio::test]
+    async fn rolls_back_forks() {
+        let client = MockClient::new();
+        let checks = Arc::new(AtomicU32::new(0));
+
+        // After the first check blocks 4 and 5 are replaced by a fork on branch 1
+        let forked = checks.clone();
+        client.respond_with("block_api.get_block", move |params| {
+            let n = params["block_num"].as_u64().unwrap() as u32;
+
+            Ok(match (n, forked.load(Ordering::SeqCst) > 1) {
+                (4, true) => json!({ "block": block_json(4, 1, 0) }),
+                (n, true) if n > 4 => json!({ "block": block_json(n, 1, 1) }),
+                (n, _) => json!({ "block": block_json(n, 0, 0) }),
+            })
+        });
+        client.respond_with("database_api.get_dynamic_global_properties", move |_| {
+            let head = 4 + checks.fetch_add(1, Ordering::SeqCst) + 1;
+            Ok(dynamic_global_properties_json(head, 1, Utc::now()))
+        });
+
+        let events: Vec<_> = stream_block_events_with_interval(
+            &client,
+            1,
+            BlockchainMode::Reversible,
+            Duration::ZERO,
+        )
+        .take(9)
+        .map(|event| match event.unwrap() {
+            BlockEvent::Block(block) => block.block_id,
+            BlockEvent::Rollback { to_block } => format!("rollback to {}", to_block),
+        })
+        .collect()
+        .await;
+
+        assert_eq!(
+            events,
+            vec![
+                block_id(1, 0),
+                block_id(2, 0),
+                block_id(3, 0),
+                block_id(4, 0),
+                block_id(5, 0),
+                "rollback to 3".to_owned(),
+                block_id(4, 1),
+                block_id(5, 1),
+                block_id(6, 1),
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn rolls_back_forks_past_remembered_blocks() {
+        let client = MockClient::new();
+        // Blocks 4 and later were replaced by a fork on branch 1
+        client.respond_with("block_api.get_block", |params| {
+            let n = params["block_num"].as_u64().unwrap() as u32;
+            let previous_branch = if n > 4 { 1 } else { 0 };
+            Ok(json!({ "block": block_json(n, 1, previous_branch) }))
+        });
+        let block = |n, branch, previous_branch| -> Block {
+            serde_json::from_value(block_json(n, branch, previous_branch)).unwrap()
+        };
+        // Only block 6 of the old branch is remembered, as when more than `MAX_FORK_DEPTH`
+        // blocks are reversible
+        let mut stream = BlockStream {
+            client: &client,
+            mode: BlockchainMode::Reversible,
+            next: 8,
+            start_at_head: false,
+            last: 7,
+            irreversible: 3,
+            buffer: VecDeque::new(),
+            delivered: VecDeque::from([(6, block_id(6, 0))]),
+            failed: false,
+        };
+
+        let event = stream.deliver(block(7, 1, 1)).await.unwrap();
+
+        assert!(matches!(event, Some(BlockEvent::Rollback { to_block: 3 })));
+        let branch: Vec<_> = stream.buffer.iter().map(|b| b.block_id.clone()).collect();
+        assert_eq!(
+            branch,
+            vec![
+                block_id(4, 1),
+                block_id(5, 1),
+                block_id(6, 1),
+                block_id(7, 1)
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn irreversible_mode_ignores_links() {
+        let client = chain(10, 0);
+        client.respond_with("block_api.get_block", |params| {
+            let n = params["block_num"].as_u64().unwrap() as u32;
+            Ok(json!({ "block": block_json(n, n, n) }))
+        });
+
+        let events: Vec<_> = stream_block_events_with_interval(
+            &client,
+            1,
+            BlockchainMode::Irreversible,
+            Duration::ZERO,
+        )
+        .take(3)
+        .collect()
+        .await;
+
+        assert!(events
+            .iter()
+            .all(|event| matches!(event, Ok(BlockEvent::Block(_)))));
+    }
+}
@@ -1,6 +1,11 @@
+use crate::block_api::block::decode_hash;
 use crate::block_api::operation::Operation;
+use crate::crypto::sha256;
 use crate::de::deserialize_hive_time;
+use crate::dec::{decode_leb128, decode_without_size, DecodeError};
+use crate::enc::{encode_to_vec, EncodeError, Writer, LEB128};
 use crate::ser::serialize_hive_time;
+use crate::{HiveDecode, HiveDecoder, HiveEncode, HiveEncoder};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
@@ -17,3 +22,96 @@ pub struct Transaction {
     pub signatures: Vec<String>,
     pub extensions: Vec<()>,
 }
+
+impl Transaction {
+    /// Id of the transaction, the first 20 bytes of the SHA-256 of the transaction without its
+    /// signatures
+    pub fn id(&self) -> Result<String, EncodeError> {
+        let v = encode_to_vec(Unsigned(self))?;
+
+        Ok(hex::encode(&sha256(v)[..20]))
+    }
+
+    /// SHA-256 of the transaction including its signatures, the leaf of the merkle tree of a block
+    pub(crate) fn merkle_digest(&self) -> Result<[u8; 32], EncodeError> {
+        Ok(sha256(encode_to_vec(self)?))
+    }
+
+    fn encode_unsigned<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        let ref_block_num = u16::try_from(self.ref_block_num).map_err(|_| {
+            EncodeError::InvalidValue(format!("ref_block_num {}", self.ref_block_num))
+        })?;
+
+        ref_block_num.encode(encoder)?;
+        self.ref_block_prefix.encode(encoder)?;
+        self.expiration.encode(encoder)?;
+        self.operations.encode(encoder)?;
+
+        // Only `void_t` exists as transaction extension
+        LEB128::from(self.extensions.len() as u64).encode(encoder)?;
+        for _ in &self.extensions {
+            LEB128::from(0u8).encode(encoder)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Encodes a transaction without its signatures
+struct Unsigned<'a>(&'a Transaction);
+
+impl HiveEncode for Unsigned<'_> {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.0.encode_unsigned(encoder)
+    }
+}
+
+impl HiveEncode for Transaction {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.encode_unsigned(encoder)?;
+
+        LEB128::from(self.signatures.len() as u64).encode(encoder)?;
+        for signature in &self.signatures {
+            let signature: [u8; 65] = decode_hash("signature", signature)?;
+            encoder.writer().write(&signature)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl HiveDecode for Transaction {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let ref_block_num = u16::decode(decoder)? as u32;
+        let ref_block_prefix = u32::decode(decoder)?;
+        let expiration = DateTime::decode(decoder)?;
+        let operations = Vec::decode(decoder)?;
+
+        let mut extensions = vec![];
+        for _ in 0..decode_leb128(decoder)? {
+            match decode_leb128(decoder)? {
+                0 => extensions.push(()),
+                index => {
+                    return Err(DecodeError::InvalidValue(format!(
+                        "transaction extension {}",
+                        index
+                    )))
+                }
+            }
+        }
+
+        let mut signatures = vec![];
+        for _ in 0..decode_leb128(decoder)? {
+            signatures.push(hex::encode(decode_without_size::<_, 65>(decoder)?));
+        }
+
+        Ok(Transaction {
+            ref_block_num,
+            ref_block_prefix,
+            expiration,
+            operations,
+            signatures,
+            extensions,
+        })
+    }
+}
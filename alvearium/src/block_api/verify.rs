@@ -0,0 +1,352 @@
+use crate::block_api::block::Block;
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{ripemd160, sha256, FromWif};
+use crate::enc::EncodeError;
+use std::fmt::{Display, Formatter};
+
+/// Why a block does not match its id, merkle root or signature
+#[derive(Debug)]
+pub enum BlockVerifyError {
+    /// A field of the block is malformed and cannot be encoded
+    Encode(EncodeError),
+    BlockIdMismatch {
+        expected: String,
+        computed: String,
+    },
+    TransactionIdMismatch {
+        index: usize,
+        expected: String,
+        computed: String,
+    },
+    /// Also returned if `transaction_ids` and `transactions` differ in length
+    TransactionCountMismatch,
+    MerkleRootMismatch {
+        expected: String,
+        computed: String,
+    },
+    /// The witness signature is malformed or was not made with `signing_key`
+    InvalidSignature,
+}
+
+impl Display for BlockVerifyError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockVerifyError::Encode(_) => f.write_str("cannot encode block"),
+            BlockVerifyError::BlockIdMismatch { expected, computed } => {
+                write!(f, "block id is {} but computed {}", expected, computed)
+            }
+            BlockVerifyError::TransactionIdMismatch {
+                index,
+                expected,
+                computed,
+            } => write!(
+                f,
+                "id of transaction {} is {} but computed {}",
+                index, expected, computed
+            ),
+            BlockVerifyError::TransactionCountMismatch => {
+                f.write_str("transaction ids do not match the transactions")
+            }
+            BlockVerifyError::MerkleRootMismatch { expected, computed } => write!(
+                f,
+                "transaction merkle root is {} but computed {}",
+                expected, computed
+            ),
+            BlockVerifyError::InvalidSignature => {
+                f.write_str("witness signature was not made with the signing key")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BlockVerifyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlockVerifyError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<EncodeError> for BlockVerifyError {
+    fn from(e: EncodeError) -> Self {
+        BlockVerifyError::Encode(e)
+    }
+}
+
+impl Block {
+    /// Computes the id of the block from its signed header, see `SignedBlockHeader::id`
+    pub fn calculate_block_id(&self) -> Result<String, EncodeError> {
+        self.signed_header().id()
+    }
+
+    /// Computes the merkle root of the transactions in the block.
+    ///
+    /// The leaves are the SHA-256 of the signed transactions, pairs are hashed using SHA-256 and
+    /// the root is the RIPEMD-160 of the last hash. A block without transactions has a root of
+    /// zeroes.
+    pub fn calculate_merkle_root(&self) -> Result<String, EncodeError> {
+        if self.transactions.is_empty() {
+            return Ok(hex::encode([0u8; 20]));
+        }
+
+        let mut hashes = self
+            .transactions
+            .iter()
+            .map(|transaction| transaction.merkle_digest())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        while hashes.len() > 1 {
+            hashes = hashes
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => sha256([left.as_slice(), right.as_slice()].concat()),
+                    [odd] => *odd,
+                    _ => unreachable!(),
+                })
+                .collect();
+        }
+
+        Ok(hex::encode(ripemd160(hashes[0])))
+    }
+
+    /// Checks that the witness signed the header with `signing_key`.
+    ///
+    /// `signing_key` is sent by the node, so to trust the block it also needs to be checked to
+    /// belong to `witness`, e.g. against the witness schedule.
+    pub fn verify_witness_signature(&self) -> Result<bool, EncodeError> {
+        let signing_key = PublicKey::from_wif(&self.signing_key)
+            .map_err(|_| EncodeError::InvalidValue(format!("signing_key {}", self.signing_key)))?;
+
+        Ok(self.signed_header().signee()? == Some(signing_key))
+    }
+
+    /// Recomputes the transaction ids, merkle root and block id and checks the witness signature,
+    /// so the block does not need to be trusted to be what the node claims it to be.
+    pub fn verify(&self) -> Result<(), BlockVerifyError> {
+        if self.transaction_ids.len() != self.transactions.len() {
+            return Err(BlockVerifyError::TransactionCountMismatch);
+        }
+
+        for (index, (transaction, expected)) in self
+            .transactions
+            .iter()
+            .zip(&self.transaction_ids)
+            .enumerate()
+        {
+            let computed = transaction.id()?;
+            if computed != *expected {
+                return Err(BlockVerifyError::TransactionIdMismatch {
+                    index,
+                    expected: expected.clone(),
+                    computed,
+                });
+            }
+        }
+
+        let computed = self.calculate_merkle_root()?;
+        if computed != self.transaction_merkle_root {
+            return Err(BlockVerifyError::MerkleRootMismatch {
+                expected: self.transaction_merkle_root.clone(),
+                computed,
+            });
+        }
+
+        if !self.verify_witness_signature()? {
+            return Err(BlockVerifyError::InvalidSignature);
+        }
+
+        let computed = self.calculate_block_id()?;
+        if computed != self.block_id {
+            return Err(BlockVerifyError::BlockIdMismatch {
+                expected: self.block_id.clone(),
+                computed,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::block_api::block::Block;
+    use crate::block_api::tests::block_id;
+    use crate::block_api::verify::BlockVerifyError;
+    use crate::crypto::private_key::PrivateKey;
+    use crate::crypto::public_key::PublicKey;
+    use crate::crypto::{ripemd160, sha256, signature_from_bytes, signature_to_bytes, IntoWif};
+    use crate::enc::encode_to_vec;
+    use hex_literal::hex;
+    use serde_json::{json, Value};
+
+    pub(crate) fn witness_key() -> PrivateKey {
+        PrivateKey::from_seed("witness").unwrap()
+    }
+
+    pub(crate) fn transfer_json(memo: &str) -> Value {
+        json!({
+            "ref_block_num": 41,
+            "ref_block_prefix": 3_926_437_650u32,
+            "expiration": "2022-10-20T10:10:00",
+            "operations": [{
+                "type": "transfer_operation",
+                "value": {
+                    "from": "alice",
+                    "to": "bob",
+                    "amount": { "amount": "1000", "precision": 3, "nai": "@@000000021" },
+                    "memo": memo
+                }
+            }],
+            "extensions": [],
+            "signatures": [hex::encode([0x1f; 65])]
+        })
+    }
+
+    /// A block after `previous` signed by `witness_key`, with correct ids and merkle root
+    ///
+    /// # Arguments
+    /// * `previous` - Id of the previous block
+    /// * `transactions` - Transactions of the block as JSON
+    pub(crate) fn signed_block(previous: &str, transactions: Vec<Value>) -> Block {
+        let mut block: Block = serde_json::from_value(json!({
+            "previous": previous,
+            "timestamp": "2022-10-20T10:00:03",
+            "witness": "witness",
+            "transaction_merkle_root": "0000000000000000000000000000000000000000",
+            "extensions": [{ "type": "version", "value": "1.27.0" }],
+            "witness_signature": hex::encode([0u8; 65]),
+            "transactions": transactions,
+            "block_id": "",
+            "signing_key": witness_key().create_public(None).to_wif(),
+            "transaction_ids": []
+        }))
+        .unwrap();
+
+        block.transaction_ids = block
+            .transactions
+            .iter()
+            .map(|transaction| transaction.id().unwrap())
+            .collect();
+        block.transaction_merkle_root = block.calculate_merkle_root().unwrap();
+
+        let digest = sha256(encode_to_vec(block.header()).unwrap());
+        let signature = witness_key().sign_digest_canonical(digest);
+        block.witness_signature = hex::encode(signature_to_bytes(&signature));
+        block.block_id = block.calculate_block_id().unwrap();
+
+        block
+    }
+
+    /// The same signature with S replaced by n - S and the other recovery id
+    fn high_s_twin(signature: &[u8]) -> Vec<u8> {
+        const ORDER: [u8; 32] =
+            hex!("fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141");
+
+        let mut twin = signature.to_vec();
+        twin[0] = 31 + ((signature[0] - 31) ^ 1);
+        let mut borrow = 0;
+        for i in (0..32).rev() {
+            let difference = ORDER[i] as i16 - signature[33 + i] as i16 - borrow;
+            twin[33 + i] = difference.rem_euclid(256) as u8;
+            borrow = (difference < 0) as i16;
+        }
+
+        twin
+    }
+
+    #[test]
+    fn verify_signed_block() {
+        let previous = block_id(99, 0);
+        let block = signed_block(
+            &previous,
+            (0..3).map(|i| transfer_json(&i.to_string())).collect(),
+        );
+
+        assert!(block.verify().is_ok());
+        assert_eq!(block.block_num(), Some(100));
+        assert_eq!(&block.block_id[..8], "00000064");
+    }
+
+    #[test]
+    fn merkle_root() {
+        let empty = signed_block(&block_id(1, 0), vec![]);
+        let single = signed_block(&block_id(1, 0), vec![transfer_json("")]);
+
+        assert_eq!(
+            empty.transaction_merkle_root,
+            "0000000000000000000000000000000000000000"
+        );
+        assert_eq!(
+            single.transaction_merkle_root,
+            hex::encode(ripemd160(single.transactions[0].merkle_digest().unwrap()))
+        );
+    }
+
+    #[test]
+    fn detect_tampering() {
+        let previous = block_id(99, 0);
+        let transactions = || vec![transfer_json("a"), transfer_json("b")];
+
+        let mut block = signed_block(&previous, transactions());
+        block.transactions[1].signatures[0] = hex::encode([0x20; 65]);
+        assert!(matches!(
+            block.verify(),
+            Err(BlockVerifyError::MerkleRootMismatch { .. })
+        ));
+
+        let mut block = signed_block(&previous, transactions());
+        block.transactions.swap(0, 1);
+        assert!(matches!(
+            block.verify(),
+            Err(BlockVerifyError::TransactionIdMismatch { index: 0, .. })
+        ));
+
+        let mut block = signed_block(&previous, transactions());
+        block.witness = "mallory".to_owned();
+        assert!(matches!(
+            block.verify(),
+            Err(BlockVerifyError::InvalidSignature)
+        ));
+
+        let mut block = signed_block(&previous, transactions());
+        block.signing_key = PrivateKey::from_seed("mallory")
+            .unwrap()
+            .create_public(None)
+            .to_wif();
+        assert!(matches!(
+            block.verify(),
+            Err(BlockVerifyError::InvalidSignature)
+        ));
+
+        // The high-S twin of the signature recovers the same key but is not canonical
+        let mut block = signed_block(&previous, transactions());
+        let signature = hex::decode(&block.witness_signature).unwrap();
+        let twin = high_s_twin(&signature);
+        assert_eq!(
+            PublicKey::recover(
+                encode_to_vec(block.header()).unwrap(),
+                &signature_from_bytes(&twin).unwrap()
+            )
+            .unwrap()
+            .to_wif(),
+            block.signing_key
+        );
+        block.witness_signature = hex::encode(twin);
+        assert!(matches!(
+            block.verify(),
+            Err(BlockVerifyError::InvalidSignature)
+        ));
+
+        let mut block = signed_block(&previous, transactions());
+        block.block_id = block_id(100, 1);
+        assert!(matches!(
+            block.verify(),
+            Err(BlockVerifyError::BlockIdMismatch { .. })
+        ));
+
+        let mut block = signed_block(&previous, transactions());
+        block.previous = "0000".to_owned();
+        assert!(matches!(block.verify(), Err(BlockVerifyError::Encode(_))));
+    }
+}
@@ -0,0 +1,650 @@
+//! Reader for the `block_log` files of hived, to read blocks without a node.
+
+use crate::block_api::block::{block_num_from_id, Block, SignedBlock};
+use crate::dec::{decode_from_slice, DecodeError};
+use crate::enc::EncodeError;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::ops::Range;
+use std::path::Path;
+
+/// The lower 48 bits of a position in the `block_log` are the offset, the upper bits are flags
+const POSITION_MASK: u64 = (1 << 48) - 1;
+
+/// Position flag of blocks compressed with zstd
+const ZSTD_FLAG: u64 = 1 << 63;
+
+/// Position flag of blocks compressed with a dictionary, whose number is in bits 48 to 55
+const DICTIONARY_FLAG: u64 = 1 << 56;
+
+/// Bits of a position that are neither offset nor a known flag
+const UNKNOWN_FLAGS: u64 = !(POSITION_MASK | ZSTD_FLAG | DICTIONARY_FLAG | (0xff << 48));
+
+/// Largest block hived accepts (`HIVE_SOFT_MAX_BLOCK_SIZE`), bounds the memory a corrupt position
+/// or compressed block can claim
+const MAX_BLOCK_SIZE: u64 = 2 * 1024 * 1024;
+
+/// Magic number zstd frames start with, hived leaves it out
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug)]
+pub enum BlockLogError {
+    Io(std::io::Error),
+    Decode(DecodeError),
+    Encode(EncodeError),
+    /// The file does not have the structure of a `block_log`
+    Corrupt(String),
+    /// The block is compressed with a dictionary that was not added with `add_dictionary`
+    MissingDictionary(u8),
+    BlockNotFound(u32),
+}
+
+impl Display for BlockLogError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BlockLogError::Io(_) => f.write_str("cannot read block_log"),
+            BlockLogError::Decode(_) => f.write_str("cannot decode block"),
+            BlockLogError::Encode(_) => f.write_str("cannot compute block ids"),
+            BlockLogError::Corrupt(reason) => write!(f, "corrupt block_log: {}", reason),
+            BlockLogError::MissingDictionary(dictionary) => {
+                write!(
+                    f,
+                    "block is compressed with missing dictionary {}",
+                    dictionary
+                )
+            }
+            BlockLogError::BlockNotFound(n) => write!(f, "block {} is not in the block_log", n),
+        }
+    }
+}
+
+impl std::error::Error for BlockLogError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            BlockLogError::Io(e) => Some(e),
+            BlockLogError::Decode(e) => Some(e),
+            BlockLogError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for BlockLogError {
+    fn from(e: std::io::Error) -> Self {
+        BlockLogError::Io(e)
+    }
+}
+
+impl From<DecodeError> for BlockLogError {
+    fn from(e: DecodeError) -> Self {
+        BlockLogError::Decode(e)
+    }
+}
+
+impl From<EncodeError> for BlockLogError {
+    fn from(e: EncodeError) -> Self {
+        BlockLogError::Encode(e)
+    }
+}
+
+/// A hived `block_log` opened for reading.
+///
+/// Every block in the file is followed by the position it starts at, which is how the blocks are
+/// found. If a `block_log.index` is next to the file it is used for the positions, otherwise they
+/// are read back from the end of the file only as far as the blocks that are read, and the number
+/// of the head block is taken from the id of the block before it.
+///
+/// Since 1.27 hived compresses blocks with zstd, marked by flags in the upper bits of their
+/// position. Most are compressed with one of the dictionaries built into hived, which have to be
+/// added with `add_dictionary` to read those blocks. hived 1.27 also replaced the index with
+/// `block_log.artifacts`, which is not read, so reading an early block of such a log reads the
+/// positions of every block after it first.
+pub struct BlockLog {
+    file: File,
+    len: u64,
+    /// Position of the blocks and their flags, from the head block back as far as they were read
+    positions: Vec<u64>,
+    /// Number of the head block, once the index or the head block was read
+    head_block_num: Option<u32>,
+    dictionaries: HashMap<u8, Vec<u8>>,
+}
+
+impl BlockLog {
+    /// Opens a `block_log`
+    ///
+    /// # Arguments
+    /// * `path` - Path of the `block_log` file
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, BlockLogError> {
+        let path = path.as_ref();
+        let file = File::open(path)?;
+        let len = file.metadata()?.len();
+        let mut block_log = Self {
+            file,
+            len,
+            positions: vec![],
+            head_block_num: None,
+            dictionaries: HashMap::new(),
+        };
+
+        // An index that is stale or does not describe the file is not used
+        match read_index(&path.with_extension("index"))? {
+            Some(mut positions)
+                if positions.last() == last_position(&mut block_log.file, len)?.as_ref()
+                    && check_positions(&positions, len).is_ok() =>
+            {
+                block_log.head_block_num = Some(positions.len() as u32);
+                positions.reverse();
+                block_log.positions = positions;
+            }
+            // Only the position of the head block, the others are read when they are needed
+            _ => block_log.walk(1)?,
+        }
+
+        Ok(block_log)
+    }
+
+    /// Adds a zstd dictionary that blocks are compressed with
+    ///
+    /// # Arguments
+    /// * `number` - Number of the dictionary as stored in the block positions
+    /// * `dictionary` - Contents of the dictionary
+    pub fn add_dictionary(&mut self, number: u8, dictionary: Vec<u8>) {
+        self.dictionaries.insert(number, dictionary);
+    }
+
+    /// Number of the last block in the log, 0 if it is empty.
+    ///
+    /// Without an index this reads the head block, which needs its dictionary if it has one.
+    pub fn head_block_num(&mut self) -> Result<u32, BlockLogError> {
+        if let Some(head_block_num) = self.head_block_num {
+            return Ok(head_block_num);
+        }
+
+        let head_block_num = match self.positions.first() {
+            Some(&position) => {
+                let previous = self
+                    .decode(position, self.len)?
+                    .signed_header
+                    .header
+                    .previous;
+                block_num_from_id(&previous)
+                    .and_then(|n| n.checked_add(1))
+                    .ok_or_else(|| {
+                        BlockLogError::Corrupt(format!("head block follows block {}", previous))
+                    })?
+            }
+            None => 0,
+        };
+        self.head_block_num = Some(head_block_num);
+
+        Ok(head_block_num)
+    }
+
+    /// Reads a block as it is stored
+    ///
+    /// # Arguments
+    /// * `block_num` - Number of the block, starting at 1
+    pub fn read_signed_block(&mut self, block_num: u32) -> Result<SignedBlock, BlockLogError> {
+        let index = self
+            .head_block_num()?
+            .checked_sub(block_num)
+            .filter(|_| block_num > 0)
+            .ok_or(BlockLogError::BlockNotFound(block_num))? as usize;
+        self.walk(index + 1)?;
+
+        let position = *self.positions.get(index).ok_or_else(|| {
+            BlockLogError::Corrupt(format!("block_log starts after block {}", block_num))
+        })?;
+        if block_num == 1 && position & POSITION_MASK != 0 {
+            return Err(BlockLogError::Corrupt(
+                "block_log has blocks before block 1".to_owned(),
+            ));
+        }
+
+        // The block ends where the position of the next block is stored
+        let end = match index.checked_sub(1) {
+            Some(next) => self.positions[next] & POSITION_MASK,
+            None => self.len,
+        };
+
+        self.decode(position, end)
+    }
+
+    /// Reads positions back from the end of the file until there are `count` or the file starts
+    fn walk(&mut self, count: usize) -> Result<(), BlockLogError> {
+        while self.positions.len() < count {
+            let end = match self.positions.last() {
+                Some(position) => position & POSITION_MASK,
+                None => self.len,
+            };
+            let Some(position) = last_position(&mut self.file, end)? else {
+                break;
+            };
+
+            check_position(position, end)?;
+            self.positions.push(position);
+        }
+
+        Ok(())
+    }
+
+    /// Reads the block at `position` whose own position is stored at `end`
+    fn decode(&mut self, position: u64, end: u64) -> Result<SignedBlock, BlockLogError> {
+        let start = position & POSITION_MASK;
+        let size = end
+            .checked_sub(start + 8)
+            .filter(|size| *size <= MAX_BLOCK_SIZE)
+            .ok_or_else(|| {
+                BlockLogError::Corrupt(format!("block at {} has a size of {}", start, end - start))
+            })?;
+        let mut bytes = vec![0; size as usize];
+        self.file.seek(SeekFrom::Start(start))?;
+        self.file.read_exact(&mut bytes)?;
+
+        if position & ZSTD_FLAG != 0 {
+            bytes = self.decompress(position, &bytes)?;
+        }
+
+        let (block, len) = decode_from_slice::<SignedBlock>(&bytes)?;
+        if len != bytes.len() {
+            return Err(BlockLogError::Corrupt(format!(
+                "block at {} has {} trailing bytes",
+                start,
+                bytes.len() - len
+            )));
+        }
+
+        Ok(block)
+    }
+
+    fn decompress(&self, position: u64, bytes: &[u8]) -> Result<Vec<u8>, BlockLogError> {
+        let mut decompressor = match position & DICTIONARY_FLAG {
+            0 => zstd::bulk::Decompressor::new()?,
+            _ => {
+                let number = (position >> 48) as u8;
+                let dictionary = self
+                    .dictionaries
+                    .get(&number)
+                    .ok_or(BlockLogError::MissingDictionary(number))?;
+                zstd::bulk::Decompressor::with_dictionary(dictionary)?
+            }
+        };
+        decompressor.include_magicbytes(bytes.starts_with(&ZSTD_MAGIC))?;
+
+        decompressor
+            .decompress(bytes, MAX_BLOCK_SIZE as usize)
+            .map_err(|e| {
+                BlockLogError::Corrupt(format!(
+                    "block at {} cannot be decompressed: {}",
+                    position & POSITION_MASK,
+                    e
+                ))
+            })
+    }
+
+    /// Reads a block with its id, signing key and transaction ids, like `block_api` returns it
+    ///
+    /// # Arguments
+    /// * `block_num` - Number of the block, starting at 1
+    pub fn read_block(&mut self, block_num: u32) -> Result<Block, BlockLogError> {
+        Ok(self.read_signed_block(block_num)?.into_block()?)
+    }
+
+    /// Reads the blocks in the range, stops at the head block
+    ///
+    /// # Arguments
+    /// * `r` - Numbers of the blocks
+    pub fn blocks(
+        &mut self,
+        r: Range<u32>,
+    ) -> impl Iterator<Item = Result<Block, BlockLogError>> + '_ {
+        let (block_nums, error) = match self.head_block_num() {
+            Ok(head_block_num) => (r.start.max(1)..r.end.min(head_block_num + 1), None),
+            Err(e) => (0..0, Some(Err(e))),
+        };
+
+        error
+            .into_iter()
+            .chain(block_nums.map(move |block_num| self.read_block(block_num)))
+    }
+}
+
+/// Reads the position stored at the end of the file, that of the last block
+fn last_position(file: &mut File, len: u64) -> Result<Option<u64>, BlockLogError> {
+    if len == 0 {
+        return Ok(None);
+    }
+    if len < 8 {
+        return Err(BlockLogError::Corrupt(format!("file of {} bytes", len)));
+    }
+
+    read_u64(file, len - 8).map(Some)
+}
+
+fn read_u64(file: &mut File, position: u64) -> Result<u64, BlockLogError> {
+    let mut bytes = [0; 8];
+    file.seek(SeekFrom::Start(position))?;
+    file.read_exact(&mut bytes)?;
+
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Checks that a block at `position` ends at `end` and has only known flags
+fn check_position(position: u64, end: u64) -> Result<(), BlockLogError> {
+    let start = position & POSITION_MASK;
+
+    if start + 8 >= end {
+        return Err(BlockLogError::Corrupt(format!(
+            "block ending at {} starts at {}",
+            end.saturating_sub(8),
+            start
+        )));
+    }
+    if position & UNKNOWN_FLAGS != 0 {
+        return Err(BlockLogError::Corrupt(format!(
+            "unknown flags of position {:x}",
+            position
+        )));
+    }
+
+    Ok(())
+}
+
+/// Checks that the positions follow each other within a file of `len` bytes
+fn check_positions(positions: &[u64], len: u64) -> Result<(), BlockLogError> {
+    let ends = positions
+        .iter()
+        .skip(1)
+        .map(|next| next & POSITION_MASK)
+        .chain([len]);
+
+    for (&position, end) in positions.iter().zip(ends) {
+        check_position(position, end)?;
+    }
+
+    Ok(())
+}
+
+/// Reads a `block_log.index`, the position of every block one after the other
+fn read_index(path: &Path) -> Result<Option<Vec<u64>>, BlockLogError> {
+    let bytes = match std::fs::read(path) {
+        Ok(bytes) => bytes,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    if bytes.len() % 8 != 0 {
+        return Err(BlockLogError::Corrupt(format!(
+            "block_log.index of {} bytes",
+            bytes.len()
+        )));
+    }
+
+    Ok(Some(
+        bytes
+            .chunks_exact(8)
+            .map(|chunk| u64::from_le_bytes(chunk.try_into().unwrap()))
+            .collect(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::block::{Block, SignedBlock};
+    use crate::block_api::tests::block_id;
+    use crate::block_api::verify::tests::{signed_block, transfer_json};
+    use crate::block_log::{BlockLog, BlockLogError};
+    use crate::enc::encode_to_vec;
+    use std::path::Path;
+
+    /// Chain of `count` blocks starting at block 1, with `i` transactions in block `i`
+    fn chain(count: u32) -> Vec<Block> {
+        let mut blocks: Vec<Block> = vec![];
+
+        for n in 1..=count {
+            let previous = match blocks.last() {
+                Some(block) => block.block_id.clone(),
+                None => block_id(0, 0),
+            };
+            let transactions = (0..n).map(|i| transfer_json(&i.to_string())).collect();
+            blocks.push(signed_block(&previous, transactions));
+        }
+
+        blocks
+    }
+
+    /// Writes the blocks like hived does, returns the positions for the index
+    fn write_block_log(path: &Path, blocks: Vec<Block>) -> Vec<u64> {
+        write_compressed_block_log(path, blocks, |_, bytes| (bytes, 0))
+    }
+
+    /// Writes the blocks stored as `store` returns them for the block number and encoded block,
+    /// with the flags to add to their position
+    fn write_compressed_block_log(
+        path: &Path,
+        blocks: Vec<Block>,
+        store: impl Fn(u32, Vec<u8>) -> (Vec<u8>, u64),
+    ) -> Vec<u64> {
+        let mut bytes = vec![];
+        let mut positions = vec![];
+
+        for (i, block) in blocks.into_iter().enumerate() {
+            let (stored, flags) = store(
+                i as u32 + 1,
+                encode_to_vec(SignedBlock::from(block)).unwrap(),
+            );
+            let position = bytes.len() as u64 | flags;
+            bytes.extend(stored);
+            bytes.extend(position.to_le_bytes());
+            positions.push(position);
+        }
+
+        std::fs::write(path, bytes).unwrap();
+
+        positions
+    }
+
+    #[test]
+    fn read_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        let blocks = chain(5);
+        let ids: Vec<_> = blocks.iter().map(|block| block.block_id.clone()).collect();
+        write_block_log(&path, blocks);
+
+        let mut block_log = BlockLog::open(&path).unwrap();
+        assert_eq!(block_log.head_block_num().unwrap(), 5);
+
+        let block = block_log.read_block(3).unwrap();
+        assert_eq!(block.block_num(), Some(3));
+        assert_eq!(block.block_id, ids[2]);
+        assert_eq!(block.transactions.len(), 3);
+        assert!(block.verify().is_ok());
+
+        let read: Vec<_> = block_log
+            .blocks(0..10)
+            .map(|block| block.unwrap().block_id)
+            .collect();
+        assert_eq!(read, ids);
+
+        assert!(matches!(
+            block_log.read_block(0),
+            Err(BlockLogError::BlockNotFound(0))
+        ));
+        assert!(matches!(
+            block_log.read_block(6),
+            Err(BlockLogError::BlockNotFound(6))
+        ));
+    }
+
+    #[test]
+    fn use_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        let positions = write_block_log(&path, chain(3));
+
+        // A stale index that does not end at the last block is ignored
+        let index: Vec<u8> = positions[..2]
+            .iter()
+            .flat_map(|p| p.to_le_bytes())
+            .collect();
+        std::fs::write(dir.path().join("block_log.index"), index).unwrap();
+        assert_eq!(BlockLog::open(&path).unwrap().head_block_num().unwrap(), 3);
+
+        let index: Vec<u8> = positions.iter().flat_map(|p| p.to_le_bytes()).collect();
+        std::fs::write(dir.path().join("block_log.index"), index).unwrap();
+        let mut block_log = BlockLog::open(&path).unwrap();
+        assert_eq!(block_log.head_block_num().unwrap(), 3);
+        assert_eq!(block_log.read_block(2).unwrap().block_num(), Some(2));
+    }
+
+    #[test]
+    fn empty_block_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        std::fs::write(&path, []).unwrap();
+
+        let mut block_log = BlockLog::open(&path).unwrap();
+        assert_eq!(block_log.head_block_num().unwrap(), 0);
+        assert_eq!(block_log.blocks(1..10).count(), 0);
+    }
+
+    #[test]
+    fn read_compressed_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        let blocks = chain(3);
+        let ids: Vec<_> = blocks.iter().map(|block| block.block_id.clone()).collect();
+        let dictionary = encode_to_vec(SignedBlock::from(chain(1).remove(0))).unwrap();
+
+        // Block 1 as it is, block 2 like hived, without the magic number and with dictionary 5,
+        // and block 3 as a zstd frame
+        let compress_dictionary = dictionary.clone();
+        write_compressed_block_log(&path, blocks, move |n, bytes| match n {
+            1 => (bytes, 0),
+            3 => (zstd::bulk::compress(&bytes, 3).unwrap(), 1 << 63),
+            _ => {
+                let mut compressor =
+                    zstd::bulk::Compressor::with_dictionary(3, &compress_dictionary).unwrap();
+                compressor.include_magicbytes(false).unwrap();
+                (
+                    compressor.compress(&bytes).unwrap(),
+                    1 << 63 | 1 << 56 | 5 << 48,
+                )
+            }
+        });
+
+        let mut block_log = BlockLog::open(&path).unwrap();
+        assert_eq!(block_log.head_block_num().unwrap(), 3);
+        assert_eq!(block_log.read_block(3).unwrap().block_id, ids[2]);
+        assert!(matches!(
+            block_log.read_block(2),
+            Err(BlockLogError::MissingDictionary(5))
+        ));
+
+        block_log.add_dictionary(5, dictionary);
+        let read: Vec<_> = block_log.blocks(1..4).map(|block| block.unwrap()).collect();
+        assert_eq!(
+            read.iter()
+                .map(|block| block.block_id.clone())
+                .collect::<Vec<_>>(),
+            ids
+        );
+        assert!(read.iter().all(|block| block.verify().is_ok()));
+    }
+
+    #[test]
+    fn ignore_corrupt_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        let mut positions = write_block_log(&path, chain(3));
+        let len = std::fs::metadata(&path).unwrap().len();
+
+        // The last position matches, the one before points past the end of the file
+        positions[1] = len + 1000;
+        let index: Vec<u8> = positions.iter().flat_map(|p| p.to_le_bytes()).collect();
+        std::fs::write(dir.path().join("block_log.index"), index).unwrap();
+
+        let mut block_log = BlockLog::open(&path).unwrap();
+        assert_eq!(block_log.head_block_num().unwrap(), 3);
+        assert_eq!(block_log.read_block(2).unwrap().block_num(), Some(2));
+    }
+
+    #[test]
+    fn reject_corrupt_blocks() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        write_block_log(&path, chain(2));
+        let bytes = std::fs::read(&path).unwrap();
+        let len = bytes.len();
+
+        let mut corrupt = bytes.clone();
+        corrupt[len - 8..].copy_from_slice(&(len as u64).to_le_bytes());
+        std::fs::write(&path, &corrupt).unwrap();
+        assert!(matches!(
+            BlockLog::open(&path),
+            Err(BlockLogError::Corrupt(_))
+        ));
+
+        // Flags hived does not write
+        let mut unknown_flags = bytes.clone();
+        unknown_flags[len - 1] |= 0x40;
+        std::fs::write(&path, &unknown_flags).unwrap();
+        assert!(matches!(
+            BlockLog::open(&path),
+            Err(BlockLogError::Corrupt(_))
+        ));
+
+        // Block 1 marked as compressed, but it is not
+        let second = u64::from_le_bytes(bytes[len - 8..].try_into().unwrap()) as usize;
+        let mut compressed = bytes;
+        compressed[second - 1] |= 0x80;
+        std::fs::write(&path, &compressed).unwrap();
+        let mut block_log = BlockLog::open(&path).unwrap();
+        assert!(block_log.read_block(2).is_ok());
+        assert!(matches!(
+            block_log.read_block(1),
+            Err(BlockLogError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn read_positions_lazily() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        let positions = write_block_log(&path, chain(3));
+        let mut bytes = std::fs::read(&path).unwrap();
+
+        // The position of block 1 points past the end of the file
+        let stored = positions[1] as usize - 8;
+        let len = bytes.len() as u64;
+        bytes[stored..stored + 8].copy_from_slice(&len.to_le_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        let mut block_log = BlockLog::open(&path).unwrap();
+        assert_eq!(block_log.head_block_num().unwrap(), 3);
+        assert_eq!(block_log.read_block(3).unwrap().block_num(), Some(3));
+        assert_eq!(block_log.read_block(2).unwrap().block_num(), Some(2));
+        assert!(matches!(
+            block_log.read_block(1),
+            Err(BlockLogError::Corrupt(_))
+        ));
+    }
+
+    #[test]
+    fn reject_partial_index() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("block_log");
+        let positions = write_block_log(&path, chain(2));
+
+        let mut index: Vec<u8> = positions.iter().flat_map(|p| p.to_le_bytes()).collect();
+        index.extend([0; 3]);
+        std::fs::write(dir.path().join("block_log.index"), index).unwrap();
+
+        assert!(matches!(
+            BlockLog::open(&path),
+            Err(BlockLogError::Corrupt(_))
+        ));
+    }
+}
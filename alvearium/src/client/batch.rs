@@ -0,0 +1,212 @@
+use crate::client::HiveClient;
+use crate::Error;
+use jsonrpsee::core::traits::ToRpcParams;
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::marker::PhantomData;
+
+/// Requests that are sent to the node together, in a single JSON-RPC batch.
+///
+/// Every added request returns a `BatchItem` to take its typed result from the `BatchResults`.
+#[derive(Default)]
+pub struct Batch {
+    requests: Vec<(String, Option<Box<RawValue>>)>,
+}
+
+/// Handle to the result of a request in a `Batch`
+pub struct BatchItem<R> {
+    index: usize,
+    result: PhantomData<fn() -> R>,
+}
+
+/// Results of a sent `Batch`
+pub struct BatchResults {
+    results: Vec<Option<Result<Value, Error>>>,
+}
+
+impl Batch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a request to the batch
+    ///
+    /// # Arguments
+    /// * `method` - Method to call, e.g. `block_api.get_block`
+    /// * `params` - Params of the request
+    pub fn add<R: DeserializeOwned, P: ToRpcParams>(
+        &mut self,
+        method: &str,
+        params: P,
+    ) -> Result<BatchItem<R>, Error> {
+        self.requests
+            .push((method.to_owned(), params.to_rpc_params()?));
+
+        Ok(BatchItem {
+            index: self.requests.len() - 1,
+            result: PhantomData,
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.requests.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.requests.is_empty()
+    }
+
+    /// Sends all requests, fails only if the batch did not reach the node or the node did not
+    /// answer every request
+    ///
+    /// # Arguments
+    /// * `client` - Client to send the batch with
+    pub async fn send<C: HiveClient + ?Sized>(self, client: &C) -> Result<BatchResults, Error> {
+        let requests = self.requests.len();
+        let results = client.batch_raw(self.requests).await?;
+
+        if results.len() != requests {
+            return Err(Error::BatchSizeMismatch {
+                requests,
+                results: results.len(),
+            });
+        }
+
+        Ok(BatchResults {
+            results: results.into_iter().map(Some).collect(),
+        })
+    }
+}
+
+impl BatchResults {
+    /// Takes the result of a request, fails with `MissingBatchResult` if `item` belongs to another
+    /// batch
+    ///
+    /// # Arguments
+    /// * `item` - Handle returned when the request was added to the batch
+    pub fn take<R: DeserializeOwned>(&mut self, item: BatchItem<R>) -> Result<R, Error> {
+        let result = self
+            .results
+            .get_mut(item.index)
+            .and_then(Option::take)
+            .ok_or(Error::MissingBatchResult(item.index))?;
+
+        Ok(serde_json::from_value(result?)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::{Batch, HiveClient, MockClient};
+    use crate::params::{EmptyArrayParams, EmptyObjectParams};
+    use crate::{Error, RpcError};
+    use async_trait::async_trait;
+    use serde_json::value::RawValue;
+    use serde_json::{json, Value};
+
+    /// Answers batches without the result of the last request
+    struct ShortBatchClient(MockClient);
+
+    #[async_trait]
+    impl HiveClient for ShortBatchClient {
+        async fn request_raw(
+            &self,
+            method: &str,
+            params: Option<Box<RawValue>>,
+        ) -> Result<Value, Error> {
+            self.0.request_raw(method, params).await
+        }
+
+        async fn batch_raw(
+            &self,
+            requests: Vec<(String, Option<Box<RawValue>>)>,
+        ) -> Result<Vec<Result<Value, Error>>, Error> {
+            let mut results = self.0.batch_raw(requests).await?;
+            results.pop();
+
+            Ok(results)
+        }
+    }
+
+    #[tokio::test]
+    async fn mixed_requests() {
+        let client = MockClient::new();
+        client.respond(
+            "database_api.get_version",
+            json!({ "chain_id": "beeab0de" }),
+        );
+        client.respond("condenser_api.get_account_count", json!(42));
+        client.respond_with("condenser_api.get_accounts", |_| {
+            Err(RpcError {
+                code: -32602,
+                message: "Invalid parameters".to_owned(),
+                data: None,
+            })
+        });
+
+        let mut batch = Batch::new();
+        let version = batch
+            .add::<Value, _>("database_api.get_version", EmptyObjectParams)
+            .unwrap();
+        let count = batch
+            .add::<u32, _>("condenser_api.get_account_count", EmptyArrayParams)
+            .unwrap();
+        let accounts = batch
+            .add::<Value, _>("condenser_api.get_accounts", EmptyArrayParams)
+            .unwrap();
+        assert_eq!(batch.len(), 3);
+
+        let mut results = batch.send(&client).await.unwrap();
+
+        assert_eq!(results.take(count).unwrap(), 42);
+        assert_eq!(results.take(version).unwrap()["chain_id"], "beeab0de");
+        assert!(matches!(
+            results.take(accounts),
+            Err(Error::Rpc(RpcError { code: -32602, .. }))
+        ));
+        assert_eq!(client.requests().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn missing_results() {
+        let client = MockClient::new();
+        client.respond("condenser_api.get_account_count", json!(42));
+
+        let mut batch = Batch::new();
+        batch
+            .add::<u32, _>("condenser_api.get_account_count", EmptyArrayParams)
+            .unwrap();
+        batch
+            .add::<u32, _>("condenser_api.get_account_count", EmptyArrayParams)
+            .unwrap();
+        assert!(matches!(
+            batch.send(&ShortBatchClient(client)).await,
+            Err(Error::BatchSizeMismatch {
+                requests: 2,
+                results: 1
+            })
+        ));
+
+        // An item of a larger batch
+        let client = MockClient::new();
+        client.respond("condenser_api.get_account_count", json!(42));
+        let mut larger = Batch::new();
+        larger
+            .add::<u32, _>("condenser_api.get_account_count", EmptyArrayParams)
+            .unwrap();
+        let second = larger
+            .add::<u32, _>("condenser_api.get_account_count", EmptyArrayParams)
+            .unwrap();
+        let mut batch = Batch::new();
+        batch
+            .add::<u32, _>("condenser_api.get_account_count", EmptyArrayParams)
+            .unwrap();
+
+        let mut results = batch.send(&client).await.unwrap();
+        assert!(matches!(
+            results.take(second),
+            Err(Error::MissingBatchResult(1))
+        ));
+    }
+}
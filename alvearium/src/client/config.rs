@@ -0,0 +1,181 @@
+use crate::client::retry::{RetryClient, RetryPolicy};
+use jsonrpsee::http_client::{HeaderMap, HttpClient, HttpClientBuilder};
+use jsonrpsee::ws_client::{WsClient, WsClientBuilder};
+use std::time::Duration;
+
+/// Settings for creating HTTP and WebSocket clients
+#[derive(Debug, Clone)]
+pub struct ClientConfig {
+    /// How long to wait for the response to a request
+    pub request_timeout: Duration,
+    /// Largest request in bytes
+    pub max_request_size: u32,
+    /// Largest response in bytes
+    pub max_response_size: u32,
+    /// Headers sent with every request, e.g. API keys
+    pub headers: HeaderMap,
+    /// How to retry read requests that did not reach the node
+    pub retry: RetryPolicy,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(60),
+            max_request_size: 10 * 1024 * 1024,
+            // A get_block_range of full blocks easily exceeds jsonrpsee's default of 10 MB
+            max_response_size: 64 * 1024 * 1024,
+            headers: HeaderMap::new(),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+impl ClientConfig {
+    /// Creates an HTTP client with these settings
+    ///
+    /// # Arguments
+    /// * `target` - Url of the node, e.g. `https://api.hive.blog:443`
+    pub fn build_http(
+        &self,
+        target: impl AsRef<str>,
+    ) -> Result<RetryClient<HttpClient>, jsonrpsee::core::Error> {
+        let client = HttpClientBuilder::default()
+            .request_timeout(self.request_timeout)
+            .max_request_size(self.max_request_size)
+            .max_response_size(self.max_response_size)
+            .set_headers(self.headers.clone())
+            .build(target)?;
+
+        Ok(RetryClient::new(client, self.retry))
+    }
+
+    /// Connects to a node over WebSocket with these settings
+    ///
+    /// # Arguments
+    /// * `target` - Url of the node, e.g. `ws://localhost:8090`
+    pub async fn build_ws(
+        &self,
+        target: impl AsRef<str>,
+    ) -> Result<RetryClient<WsClient>, jsonrpsee::core::Error> {
+        let client = WsClientBuilder::default()
+            .request_timeout(self.request_timeout)
+            .max_request_size(self.max_request_size)
+            .max_response_size(self.max_response_size)
+            .set_headers(self.headers.clone())
+            .build(target)
+            .await?;
+
+        Ok(RetryClient::new(client, self.retry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::tests::{LocalNode, RpcResult};
+    use crate::client::{ClientConfig, HiveClient, RetryPolicy};
+    use crate::database_api::get_version;
+    use crate::Error;
+    use jsonrpsee::server::RpcModule;
+    use serde_json::value::RawValue;
+    use serde_json::{json, Value};
+    use std::time::{Duration, Instant};
+
+    #[tokio::test]
+    async fn retries_unreachable_node() {
+        let mut config = ClientConfig::default();
+        config
+            .headers
+            .insert("x-api-key", "secret".parse().unwrap());
+        config.retry.max_retries = 2;
+        config.retry.initial_backoff = Duration::from_millis(20);
+        let client = config.build_http("http://127.0.0.1:1").unwrap();
+
+        let start = Instant::now();
+        let result = get_version(&client).await;
+
+        assert!(matches!(result, Err(Error::Transport(_))));
+        // Two retries, waiting at least half of 20 and 40 ms
+        assert!(start.elapsed() >= Duration::from_millis(30));
+    }
+
+    #[tokio::test]
+    async fn sends_headers() {
+        let mut methods = RpcModule::new(());
+        methods
+            .register_method("database_api.get_version", |_, _| {
+                json!({
+                    "blockchain_version": "1.26.0",
+                    "hive_revision": "",
+                    "fc_revision": "",
+                    "chain_id": ""
+                })
+            })
+            .unwrap();
+        let node = LocalNode::start(methods).await;
+        let mut config = ClientConfig::default();
+        config
+            .headers
+            .insert("x-api-key", "secret".parse().unwrap());
+        let client = config.build_http(&node.url).unwrap();
+
+        get_version(&client).await.unwrap();
+
+        let received = node.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(received[0].headers["x-api-key"], "secret");
+        assert_eq!(received[0].methods, ["database_api.get_version"]);
+    }
+
+    async fn echo(client: &impl HiveClient, params: Value) -> Result<Value, Error> {
+        let params = RawValue::from_string(params.to_string()).unwrap();
+        client.request_raw("test.echo", Some(params)).await
+    }
+
+    #[tokio::test]
+    async fn separate_size_limits() {
+        // Answers with 1000 bytes for every param
+        let mut methods = RpcModule::new(());
+        methods
+            .register_method("test.echo", |params, _| -> RpcResult<String> {
+                Ok("x".repeat(1000 * params.parse::<Vec<Value>>()?.len()))
+            })
+            .unwrap();
+        let node = LocalNode::start(methods).await;
+        // Small requests, large responses
+        let config = ClientConfig {
+            max_request_size: 200,
+            max_response_size: 4000,
+            retry: RetryPolicy {
+                initial_backoff: Duration::from_millis(1),
+                ..RetryPolicy::default()
+            },
+            ..ClientConfig::default()
+        };
+        let client = config.build_http(&node.url).unwrap();
+        assert!(echo(&client, json!([1, 2, 3])).await.is_ok());
+        assert!(matches!(
+            echo(&client, json!(["y".repeat(200)])).await,
+            Err(Error::Transport(_))
+        ));
+        assert!(matches!(
+            echo(&client, json!([1, 2, 3, 4, 5])).await,
+            Err(Error::Transport(_))
+        ));
+        // The large request never reached the node and the large response was not retried
+        assert_eq!(node.received().len(), 2);
+
+        // Large requests, small responses
+        let config = ClientConfig {
+            max_request_size: 4000,
+            max_response_size: 2500,
+            ..ClientConfig::default()
+        };
+        let client = config.build_http(&node.url).unwrap();
+        assert!(echo(&client, json!(["y".repeat(2000), 2])).await.is_ok());
+        assert!(matches!(
+            echo(&client, json!([1, 2, 3])).await,
+            Err(Error::Transport(_))
+        ));
+    }
+}
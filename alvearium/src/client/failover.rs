@@ -0,0 +1,509 @@
+use crate::client::{is_idempotent, HiveClient};
+use crate::create_default_client;
+use crate::database_api::get_dynamic_global_properties;
+use crate::Error;
+use async_trait::async_trait;
+use chrono::Utc;
+use futures::future::join_all;
+use jsonrpsee::http_client::HttpClient;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Settings of a `FailoverClient`
+#[derive(Debug, Clone, Copy)]
+pub struct FailoverConfig {
+    /// How long the result of a health check is used, the next request checks the nodes again
+    pub health_check_interval: Duration,
+    /// Nodes that do not answer the health check within this time are considered unhealthy
+    pub health_check_timeout: Duration,
+    /// Nodes whose head block is older than this are considered stuck
+    pub max_head_age: Duration,
+    /// Nodes at most this many blocks behind the most recent node take turns serving requests
+    pub max_blocks_behind: u32,
+}
+
+impl Default for FailoverConfig {
+    fn default() -> Self {
+        Self {
+            health_check_interval: Duration::from_secs(30),
+            health_check_timeout: Duration::from_secs(5),
+            max_head_age: Duration::from_secs(30),
+            max_blocks_behind: 2,
+        }
+    }
+}
+
+/// State of a node as seen by the last health check or request
+#[derive(Debug, Clone)]
+pub struct NodeHealth {
+    pub url: String,
+    /// Head block of the node, `None` if it was never reached
+    pub head_block_number: Option<u32>,
+    pub healthy: bool,
+}
+
+struct Node<C> {
+    client: C,
+    health: Mutex<NodeHealth>,
+}
+
+/// Client that spreads requests over several nodes and fails over when one goes down.
+///
+/// The nodes are health checked with `get_dynamic_global_properties`, a node is healthy when it
+/// answers and its head block is recent. Requests go to the healthy node with the most recent head
+/// block, nodes that are equally up to date take turns. Read requests that fail to reach a node
+/// are retried on the next one, broadcasts are never retried because the first node might have
+/// received them.
+pub struct FailoverClient<C = HttpClient> {
+    nodes: Vec<Node<C>>,
+    config: FailoverConfig,
+    last_check: Mutex<Option<Instant>>,
+    turn: AtomicUsize,
+}
+
+impl FailoverClient<HttpClient> {
+    /// Creates a client for the given node urls with the default `FailoverConfig`
+    ///
+    /// # Arguments
+    /// * `urls` - Urls of the nodes, e.g. `https://api.hive.blog:443`
+    pub fn new(
+        urls: impl IntoIterator<Item = impl AsRef<str>>,
+    ) -> Result<Self, jsonrpsee::core::Error> {
+        Self::with_config(urls, FailoverConfig::default())
+    }
+
+    /// Creates a client for the given node urls
+    ///
+    /// # Arguments
+    /// * `urls` - Urls of the nodes, e.g. `https://api.hive.blog:443`
+    /// * `config` - When nodes are considered healthy
+    pub fn with_config(
+        urls: impl IntoIterator<Item = impl AsRef<str>>,
+        config: FailoverConfig,
+    ) -> Result<Self, jsonrpsee::core::Error> {
+        let nodes = urls
+            .into_iter()
+            .map(|url| Ok((url.as_ref().to_owned(), create_default_client(url)?)))
+            .collect::<Result<Vec<_>, jsonrpsee::core::Error>>()?;
+
+        Ok(Self::from_clients(nodes, config))
+    }
+}
+
+impl<C: HiveClient> FailoverClient<C> {
+    /// Creates a client from already connected clients, e.g. `WsClient`s
+    ///
+    /// # Arguments
+    /// * `nodes` - Url and client of every node, the url is only used in `health`
+    /// * `config` - When nodes are considered healthy
+    pub fn from_clients(
+        nodes: impl IntoIterator<Item = (String, C)>,
+        config: FailoverConfig,
+    ) -> Self {
+        let nodes = nodes
+            .into_iter()
+            .map(|(url, client)| Node {
+                client,
+                health: Mutex::new(NodeHealth {
+                    url,
+                    head_block_number: None,
+                    healthy: false,
+                }),
+            })
+            .collect();
+
+        Self {
+            nodes,
+            config,
+            last_check: Mutex::new(None),
+            turn: AtomicUsize::new(0),
+        }
+    }
+
+    /// Checks all nodes now, instead of waiting for the next request after `health_check_interval`
+    pub async fn check_health(&self) {
+        *self.last_check.lock().unwrap() = Some(Instant::now());
+
+        let checks = self.nodes.iter().map(|node| async move {
+            // A node that accepts the connection but never answers must not hold up the others
+            let result = tokio::time::timeout(
+                self.config.health_check_timeout,
+                get_dynamic_global_properties(&node.client),
+            )
+            .await;
+            let mut health = node.health.lock().unwrap();
+
+            match result {
+                Ok(Ok(properties)) => {
+                    let age = (Utc::now() - properties.time).to_std().unwrap_or_default();
+                    health.head_block_number = Some(properties.head_block_number);
+                    health.healthy = age <= self.config.max_head_age;
+                }
+                Ok(Err(_)) | Err(_) => health.healthy = false,
+            }
+        });
+
+        join_all(checks).await;
+    }
+
+    /// Health of every node, in the order they were given
+    pub fn health(&self) -> Vec<NodeHealth> {
+        self.nodes
+            .iter()
+            .map(|node| node.health.lock().unwrap().clone())
+            .collect()
+    }
+
+    async fn check_health_if_due(&self) {
+        let due = match *self.last_check.lock().unwrap() {
+            Some(last_check) => last_check.elapsed() >= self.config.health_check_interval,
+            None => true,
+        };
+
+        if due {
+            self.check_health().await;
+        }
+    }
+
+    /// Nodes in the order they should be tried: the up to date nodes starting with the one whose
+    /// turn it is, the healthy nodes that are behind and the unhealthy nodes as a last resort
+    fn candidates(&self) -> Vec<&Node<C>> {
+        let health = self.health();
+        let mut healthy: Vec<usize> = (0..self.nodes.len())
+            .filter(|&i| health[i].healthy)
+            .collect();
+        healthy.sort_by_key(|&i| std::cmp::Reverse(health[i].head_block_number));
+
+        let best = healthy
+            .first()
+            .and_then(|&i| health[i].head_block_number)
+            .unwrap_or_default();
+        let up_to_date = healthy
+            .iter()
+            .take_while(|&&i| {
+                health[i].head_block_number.unwrap_or_default() + self.config.max_blocks_behind
+                    >= best
+            })
+            .count();
+        if up_to_date > 0 {
+            let turn = self.turn.fetch_add(1, Ordering::Relaxed) % up_to_date;
+            healthy[..up_to_date].rotate_left(turn);
+        }
+
+        healthy
+            .into_iter()
+            .chain((0..self.nodes.len()).filter(|&i| !health[i].healthy))
+            .map(|i| &self.nodes[i])
+            .collect()
+    }
+}
+
+#[async_trait]
+impl<C: HiveClient> HiveClient for FailoverClient<C> {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        self.check_health_if_due().await;
+
+        let mut last_error = None;
+
+        for node in self.candidates() {
+            match node.client.request_raw(method, params.clone()).await {
+                Err(Error::Transport(e)) => {
+                    node.health.lock().unwrap().healthy = false;
+
+                    if !is_idempotent(method) {
+                        return Err(Error::Transport(e));
+                    }
+                    last_error = Some(Error::Transport(e));
+                }
+                result => return result,
+            }
+        }
+
+        Err(last_error.unwrap_or_else(no_nodes))
+    }
+
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        self.check_health_if_due().await;
+
+        let idempotent = requests.iter().all(|(method, _)| is_idempotent(method));
+        let mut last_error = None;
+
+        for node in self.candidates() {
+            match node.client.batch_raw(requests.clone()).await {
+                Err(Error::Transport(e)) => {
+                    node.health.lock().unwrap().healthy = false;
+
+                    if !idempotent {
+                        return Err(Error::Transport(e));
+                    }
+                    last_error = Some(Error::Transport(e));
+                }
+                result => return result,
+            }
+        }
+
+        Err(last_error.unwrap_or_else(no_nodes))
+    }
+}
+
+fn no_nodes() -> Error {
+    Error::Transport(jsonrpsee::core::Error::Custom(
+        "no nodes configured".to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::failover::{FailoverClient, FailoverConfig};
+    use crate::client::tests::LocalNode;
+    use crate::client::{ClientConfig, HiveClient, MockClient, RetryPolicy};
+    use crate::condenser_api::broadcast_transaction;
+    use crate::create_default_client;
+    use crate::database_api::get_version;
+    use crate::database_api::tests::dynamic_global_properties_json;
+    use crate::Error;
+    use async_trait::async_trait;
+    use chrono::{Duration, Utc};
+    use jsonrpsee::server::RpcModule;
+    use serde_json::value::RawValue;
+    use serde_json::{json, Value};
+    use std::sync::Arc;
+    use std::time::Instant;
+    use tokio::net::TcpListener;
+
+    /// Passes health checks, but the connection drops for every other request
+    struct FlakyClient(MockClient);
+
+    #[async_trait]
+    impl HiveClient for FlakyClient {
+        async fn request_raw(
+            &self,
+            method: &str,
+            params: Option<Box<RawValue>>,
+        ) -> Result<Value, Error> {
+            let result = self.0.request_raw(method, params).await;
+
+            match method {
+                "database_api.get_dynamic_global_properties" => result,
+                _ => Err(Error::Transport(jsonrpsee::core::Error::RequestTimeout)),
+            }
+        }
+    }
+
+    fn node(head_block_number: u32, head_age: Duration) -> Arc<MockClient> {
+        let client = MockClient::new();
+        client.respond(
+            "database_api.get_dynamic_global_properties",
+            dynamic_global_properties_json(head_block_number, 1, Utc::now() - head_age),
+        );
+        client.respond(
+            "database_api.get_version",
+            version_json(&head_block_number.to_string()),
+        );
+        client.respond("condenser_api.broadcast_transaction", json!({}));
+
+        Arc::new(client)
+    }
+
+    fn version_json(hive_revision: &str) -> Value {
+        json!({
+            "blockchain_version": "1.26.0",
+            "hive_revision": hive_revision,
+            "fc_revision": "",
+            "chain_id": ""
+        })
+    }
+
+    fn failover(nodes: Vec<Box<dyn HiveClient>>) -> FailoverClient<Box<dyn HiveClient>> {
+        let nodes = nodes
+            .into_iter()
+            .enumerate()
+            .map(|(i, node)| (format!("node{}", i), node));
+
+        FailoverClient::from_clients(nodes, FailoverConfig::default())
+    }
+
+    #[tokio::test]
+    async fn routes_to_most_recent_healthy_node() {
+        let behind = node(100, Duration::zero());
+        let stuck = node(200, Duration::minutes(10));
+        let recent = node(110, Duration::zero());
+        let client = failover(vec![
+            Box::new(behind.clone()),
+            Box::new(stuck.clone()),
+            Box::new(recent.clone()),
+        ]);
+
+        let version = get_version(&client).await.unwrap();
+
+        assert_eq!(version.hive_revision, "110");
+        let health = client.health();
+        assert_eq!(health[0].head_block_number, Some(100));
+        assert!(health[0].healthy);
+        assert!(!health[1].healthy);
+        assert!(health[2].healthy);
+    }
+
+    #[tokio::test]
+    async fn rotates_between_up_to_date_nodes() {
+        let first = node(100, Duration::zero());
+        let second = node(101, Duration::zero());
+        let client = failover(vec![Box::new(first.clone()), Box::new(second.clone())]);
+
+        for _ in 0..4 {
+            get_version(&client).await.unwrap();
+        }
+
+        let versions = |node: &MockClient| {
+            node.requests()
+                .iter()
+                .filter(|(method, _)| method == "database_api.get_version")
+                .count()
+        };
+        assert_eq!(versions(&first), 2);
+        assert_eq!(versions(&second), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_reads_but_not_broadcasts() {
+        let flaky = FlakyClient(Arc::try_unwrap(node(110, Duration::zero())).ok().unwrap());
+        let fallback = node(100, Duration::zero());
+        let client = failover(vec![Box::new(flaky), Box::new(fallback.clone())]);
+
+        assert_eq!(get_version(&client).await.unwrap().hive_revision, "100");
+        assert!(!client.health()[0].healthy);
+
+        // The flaky node is the most recent again after the next health check
+        client.check_health().await;
+        assert!(matches!(
+            broadcast_transaction(&client, &[]).await,
+            Err(Error::Transport(_))
+        ));
+        assert!(!fallback
+            .requests()
+            .iter()
+            .any(|(method, _)| method == "condenser_api.broadcast_transaction"));
+    }
+
+    #[tokio::test]
+    async fn unreachable_node() {
+        let down = create_default_client("http://127.0.0.1:1").unwrap();
+        let up = node(100, Duration::zero());
+        let client = failover(vec![Box::new(down), Box::new(up)]);
+
+        assert_eq!(get_version(&client).await.unwrap().hive_revision, "100");
+        assert!(!client.health()[0].healthy);
+        assert_eq!(client.health()[0].head_block_number, None);
+    }
+
+    /// Methods of a node with the head block `head_block_number`, `get_version` answers with
+    /// `hive_revision`
+    fn local_methods(head_block_number: u32, hive_revision: &'static str) -> RpcModule<()> {
+        let mut methods = RpcModule::new(());
+        methods
+            .register_method("database_api.get_dynamic_global_properties", move |_, _| {
+                dynamic_global_properties_json(head_block_number, 1, Utc::now())
+            })
+            .unwrap();
+        methods
+            .register_method("database_api.get_version", move |_, _| {
+                version_json(hive_revision)
+            })
+            .unwrap();
+        methods
+            .register_method("condenser_api.broadcast_transaction", |_, _| json!({}))
+            .unwrap();
+
+        methods
+    }
+
+    #[tokio::test]
+    async fn fails_over_local_nodes() {
+        // Answers health checks, but times out on every other request
+        let mut methods = RpcModule::new(());
+        methods
+            .register_method("database_api.get_dynamic_global_properties", |_, _| {
+                dynamic_global_properties_json(110, 1, Utc::now())
+            })
+            .unwrap();
+        for method in [
+            "database_api.get_version",
+            "condenser_api.broadcast_transaction",
+        ] {
+            methods
+                .register_async_method(method, |_, _| async {
+                    tokio::time::sleep(std::time::Duration::from_secs(10)).await;
+                    json!({})
+                })
+                .unwrap();
+        }
+        let slow = LocalNode::start(methods).await;
+        let fallback = LocalNode::start(local_methods(100, "fallback")).await;
+        let config = ClientConfig {
+            request_timeout: std::time::Duration::from_millis(200),
+            retry: RetryPolicy::none(),
+            ..ClientConfig::default()
+        };
+        let clients = [&slow, &fallback]
+            .map(|node| (node.url.clone(), config.build_http(&node.url).unwrap()));
+        let client = FailoverClient::from_clients(clients, FailoverConfig::default());
+
+        assert_eq!(
+            get_version(&client).await.unwrap().hive_revision,
+            "fallback"
+        );
+        assert!(!client.health()[0].healthy);
+        assert_eq!(client.health()[0].url, slow.url);
+
+        client.check_health().await;
+        assert!(matches!(
+            broadcast_transaction(&client, &[]).await,
+            Err(Error::Transport(_))
+        ));
+        let methods = |node: &LocalNode| -> Vec<String> {
+            node.received()
+                .into_iter()
+                .flat_map(|request| request.methods)
+                .collect()
+        };
+        assert!(methods(&slow).contains(&"condenser_api.broadcast_transaction".to_owned()));
+        assert!(!methods(&fallback).contains(&"condenser_api.broadcast_transaction".to_owned()));
+    }
+
+    #[tokio::test]
+    async fn health_check_timeout() {
+        // Accepts connections and never answers
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let hanging = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut connections = vec![];
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+        let up = LocalNode::start(local_methods(100, "up")).await;
+        let config = FailoverConfig {
+            health_check_timeout: std::time::Duration::from_millis(200),
+            ..FailoverConfig::default()
+        };
+        let client = FailoverClient::with_config([&hanging, &up.url], config).unwrap();
+
+        let start = Instant::now();
+        client.check_health().await;
+
+        assert!(start.elapsed() < std::time::Duration::from_secs(5));
+        assert!(!client.health()[0].healthy);
+        assert!(client.health()[1].healthy);
+        assert_eq!(get_version(&client).await.unwrap().hive_revision, "up");
+    }
+}
@@ -0,0 +1,122 @@
+use crate::client::HiveClient;
+use crate::params::RawParams;
+use crate::{Error, RpcError};
+use async_trait::async_trait;
+use jsonrpsee::core::client::ClientT;
+use jsonrpsee::core::params::BatchRequestBuilder;
+use jsonrpsee::http_client::HttpClient;
+use jsonrpsee::ws_client::WsClient;
+use serde_json::value::RawValue;
+use serde_json::Value;
+
+/// Sends the requests as one JSON-RPC batch
+async fn batch<C: ClientT + Sync>(
+    client: &C,
+    requests: Vec<(String, Option<Box<RawValue>>)>,
+) -> Result<Vec<Result<Value, Error>>, Error> {
+    // jsonrpsee refuses to send empty batches
+    if requests.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let mut batch = BatchRequestBuilder::new();
+    for (method, params) in &requests {
+        batch.insert(method, RawParams(params.clone()))?;
+    }
+
+    let response = client.batch_request::<Value>(batch).await?;
+
+    Ok(response
+        .into_iter()
+        .map(|result| result.map_err(|e| Error::Rpc(RpcError::from_error_object(&e))))
+        .collect())
+}
+
+#[async_trait]
+impl HiveClient for HttpClient {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        Ok(self.request(method, RawParams(params)).await?)
+    }
+
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        batch(self, requests).await
+    }
+}
+
+#[async_trait]
+impl HiveClient for WsClient {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        Ok(self.request(method, RawParams(params)).await?)
+    }
+
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        batch(self, requests).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::tests::{LocalNode, RpcResult};
+    use crate::client::Batch;
+    use crate::create_default_client;
+    use crate::params::EmptyArrayParams;
+    use crate::{Error, RpcError};
+    use jsonrpsee::server::RpcModule;
+    use jsonrpsee::types::ErrorObject;
+
+    #[tokio::test]
+    async fn batch_in_one_request() {
+        // Answers with the method and fails the second request
+        let mut methods = RpcModule::new(());
+        for method in ["test.first", "test.third"] {
+            methods.register_method(method, move |_, _| method).unwrap();
+        }
+        methods
+            .register_method("test.fail", |_, _| -> RpcResult<()> {
+                Err(ErrorObject::owned(-32602, "Invalid parameters", None::<()>))
+            })
+            .unwrap();
+        let node = LocalNode::start(methods).await;
+        let client = create_default_client(&node.url).unwrap();
+
+        let mut batch = Batch::new();
+        let first = batch
+            .add::<String, _>("test.first", EmptyArrayParams)
+            .unwrap();
+        let fail = batch
+            .add::<String, _>("test.fail", EmptyArrayParams)
+            .unwrap();
+        let third = batch
+            .add::<String, _>("test.third", EmptyArrayParams)
+            .unwrap();
+        let mut results = batch.send(&client).await.unwrap();
+
+        assert_eq!(results.take(first).unwrap(), "test.first");
+        assert!(matches!(
+            results.take(fail),
+            Err(Error::Rpc(RpcError { code: -32602, .. }))
+        ));
+        assert_eq!(results.take(third).unwrap(), "test.third");
+
+        let received = node.received();
+        assert_eq!(received.len(), 1);
+        assert_eq!(
+            received[0].methods,
+            ["test.first", "test.fail", "test.third"]
+        );
+    }
+}
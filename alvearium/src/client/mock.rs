@@ -0,0 +1,149 @@
+use crate::client::HiveClient;
+use crate::{Error, RpcError};
+use async_trait::async_trait;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+type Handler = Box<dyn Fn(Value) -> Result<Value, RpcError> + Send + Sync>;
+
+/// In-memory client that answers requests with canned responses, for tests.
+///
+/// Methods without a response fail like hived does for unknown methods. Every request is recorded
+/// and can be inspected with `requests`.
+#[derive(Default)]
+pub struct MockClient {
+    handlers: Mutex<HashMap<String, Handler>>,
+    requests: Mutex<Vec<(String, Value)>>,
+}
+
+impl MockClient {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Answers every request for `method` with the same result
+    ///
+    /// # Arguments
+    /// * `method` - Method to answer, e.g. `block_api.get_block`
+    /// * `result` - `result` of the response
+    pub fn respond(&self, method: impl Into<String>, result: Value) {
+        self.respond_with(method, move |_| Ok(result.clone()));
+    }
+
+    /// Answers requests for `method` by calling `handler` with the params
+    ///
+    /// # Arguments
+    /// * `method` - Method to answer, e.g. `block_api.get_block`
+    /// * `handler` - Returns the `result` of the response or the error of the node
+    pub fn respond_with(
+        &self,
+        method: impl Into<String>,
+        handler: impl Fn(Value) -> Result<Value, RpcError> + Send + Sync + 'static,
+    ) {
+        self.handlers
+            .lock()
+            .unwrap()
+            .insert(method.into(), Box::new(handler));
+    }
+
+    /// Requests received so far as method and params, `Value::Null` if there were no params
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl HiveClient for MockClient {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        let params = match params {
+            Some(params) => serde_json::from_str(params.get())?,
+            None => Value::Null,
+        };
+        self.requests
+            .lock()
+            .unwrap()
+            .push((method.to_owned(), params.clone()));
+
+        match self.handlers.lock().unwrap().get(method) {
+            Some(handler) => Ok(handler(params)?),
+            None => Err(Error::Rpc(RpcError {
+                code: -32601,
+                message: format!("Could not find method {}", method),
+                data: None,
+            })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::get_block_header;
+    use crate::client::{request, MockClient};
+    use crate::params::EmptyObjectParams;
+    use crate::{Error, RpcError};
+    use serde_json::{json, Value};
+
+    #[tokio::test]
+    async fn respond_and_record() {
+        let client = MockClient::new();
+        client.respond("test.ping", json!("pong"));
+
+        let response: String = request(&client, "test.ping", EmptyObjectParams)
+            .await
+            .unwrap();
+
+        assert_eq!(response, "pong");
+        assert_eq!(client.requests(), vec![("test.ping".to_owned(), json!({}))]);
+    }
+
+    #[tokio::test]
+    async fn api_function_with_mock() {
+        let client = MockClient::new();
+        client.respond_with("block_api.get_block_header", |params| {
+            assert_eq!(params, json!({ "block_num": 1 }));
+            Ok(json!({
+                "header": {
+                    "previous": "0000000000000000000000000000000000000000",
+                    "timestamp": "2016-03-24T16:05:00",
+                    "witness": "initminer",
+                    "transaction_merkle_root": "0000000000000000000000000000000000000000",
+                    "extensions": []
+                }
+            }))
+        });
+
+        let header = get_block_header(&client, 1).await.unwrap();
+
+        assert_eq!(header.witness, "initminer");
+    }
+
+    #[tokio::test]
+    async fn node_errors() {
+        let client = MockClient::new();
+        client.respond_with("test.fail", |_| {
+            Err(RpcError {
+                code: -32000,
+                message: "Assert Exception".to_owned(),
+                data: None,
+            })
+        });
+
+        let failed = request::<_, _, Value>(&client, "test.fail", EmptyObjectParams).await;
+        let unknown = request::<_, _, Value>(&client, "test.unknown", EmptyObjectParams).await;
+
+        assert!(matches!(
+            failed,
+            Err(Error::Rpc(RpcError { code: -32000, .. }))
+        ));
+        assert!(matches!(
+            unknown,
+            Err(Error::Rpc(RpcError { code: -32601, .. }))
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+mod batch;
+mod config;
+mod failover;
+mod jsonrpsee_client;
+mod mock;
+mod retry;
+
+pub use batch::{Batch, BatchItem, BatchResults};
+pub use config::ClientConfig;
+pub use failover::{FailoverClient, FailoverConfig, NodeHealth};
+pub use mock::MockClient;
+pub use retry::{RetryClient, RetryPolicy};
+
+use crate::Error;
+use async_trait::async_trait;
+use jsonrpsee::core::traits::ToRpcParams;
+use serde::de::DeserializeOwned;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::sync::Arc;
+
+/// Connection to a Hive node that the API functions send their requests through.
+///
+/// Implemented for jsonrpsee's `HttpClient` and `WsClient`, and for `MockClient` to use in tests.
+/// Implement it yourself to use another transport or to wrap requests with middleware.
+#[async_trait]
+pub trait HiveClient: Send + Sync {
+    /// Sends a JSON-RPC request and returns the `result` of the response
+    ///
+    /// # Arguments
+    /// * `method` - Method to call, e.g. `block_api.get_block`
+    /// * `params` - Serialized params, `None` to send no params
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error>;
+
+    /// Sends several requests at once and returns their results in the same order
+    ///
+    /// Only fails as a whole if the batch did not reach the node. The default implementation sends
+    /// the requests one after another, transports that support JSON-RPC batches send them in a
+    /// single round trip.
+    ///
+    /// # Arguments
+    /// * `requests` - Method and serialized params of every request
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        let mut results = Vec::with_capacity(requests.len());
+
+        for (method, params) in requests {
+            results.push(self.request_raw(&method, params).await);
+        }
+
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl<C: HiveClient + ?Sized> HiveClient for &C {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        (**self).request_raw(method, params).await
+    }
+
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        (**self).batch_raw(requests).await
+    }
+}
+
+#[async_trait]
+impl<C: HiveClient + ?Sized> HiveClient for Arc<C> {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        (**self).request_raw(method, params).await
+    }
+
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        (**self).batch_raw(requests).await
+    }
+}
+
+#[async_trait]
+impl<C: HiveClient + ?Sized> HiveClient for Box<C> {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        (**self).request_raw(method, params).await
+    }
+
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        (**self).batch_raw(requests).await
+    }
+}
+
+/// Whether a request can be sent again when it is unknown if the node received it.
+///
+/// Broadcasts are not, the node might have received the transaction and a second broadcast would
+/// fail as a duplicate.
+pub(crate) fn is_idempotent(method: &str) -> bool {
+    !method.contains("broadcast")
+}
+
+/// Sends a request through any client and deserializes the result.
+///
+/// Use it to call methods this crate has no function for.
+///
+/// # Arguments
+/// * `client` - Client to send the request with
+/// * `method` - Method to call, e.g. `block_api.get_block`
+/// * `params` - Params of the request
+pub async fn request<C, P, R>(client: &C, method: &str, params: P) -> Result<R, Error>
+where
+    C: HiveClient + ?Sized,
+    P: ToRpcParams + Send,
+    R: DeserializeOwned,
+{
+    let result = client.request_raw(method, params.to_rpc_params()?).await?;
+
+    Ok(serde_json::from_value(result)?)
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use jsonrpsee::server::logger::{
+        Headers, HttpRequest, Logger, MethodKind, Params, SuccessOrError, TransportProtocol,
+    };
+    use jsonrpsee::server::{RpcModule, ServerBuilder, ServerHandle};
+    use jsonrpsee::types::ErrorObjectOwned;
+    use std::net::SocketAddr;
+    use std::sync::{Arc, Mutex};
+
+    /// Result of a method served by a `LocalNode`
+    pub(crate) type RpcResult<T> = Result<T, ErrorObjectOwned>;
+
+    /// HTTP request received by a `LocalNode`
+    #[derive(Debug, Clone)]
+    pub(crate) struct ReceivedRequest {
+        pub headers: Headers,
+        /// Methods of the calls in the request, several for a batch
+        pub methods: Vec<String>,
+    }
+
+    /// Records the requests a `LocalNode` receives
+    #[derive(Clone, Default)]
+    struct Recorder(Arc<Mutex<Vec<ReceivedRequest>>>);
+
+    impl Logger for Recorder {
+        type Instant = ();
+
+        fn on_connect(&self, _: SocketAddr, request: &HttpRequest, _: TransportProtocol) {
+            self.0.lock().unwrap().push(ReceivedRequest {
+                headers: request.headers().clone(),
+                methods: vec![],
+            });
+        }
+
+        fn on_request(&self, _: TransportProtocol) {}
+
+        fn on_call(&self, method: &str, _: Params, _: MethodKind, _: TransportProtocol) {
+            if let Some(request) = self.0.lock().unwrap().last_mut() {
+                request.methods.push(method.to_owned());
+            }
+        }
+
+        fn on_result(&self, _: &str, _: SuccessOrError, _: (), _: TransportProtocol) {}
+
+        fn on_response(&self, _: &str, _: (), _: TransportProtocol) {}
+
+        fn on_disconnect(&self, _: SocketAddr, _: TransportProtocol) {}
+    }
+
+    /// JSON-RPC node listening on a local port, for tests that need a real transport
+    pub(crate) struct LocalNode {
+        pub url: String,
+        recorder: Recorder,
+        _handle: ServerHandle,
+    }
+
+    impl LocalNode {
+        /// Starts a node that serves `methods` until it is dropped
+        pub(crate) async fn start(methods: RpcModule<()>) -> Self {
+            let recorder = Recorder::default();
+            let server = ServerBuilder::default()
+                .set_logger(recorder.clone())
+                .build("127.0.0.1:0")
+                .await
+                .unwrap();
+            let url = format!("http://{}", server.local_addr().unwrap());
+            let handle = server.start(methods);
+
+            Self {
+                url,
+                recorder,
+                _handle: handle,
+            }
+        }
+
+        pub(crate) fn received(&self) -> Vec<ReceivedRequest> {
+            self.recorder.0.lock().unwrap().clone()
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::client::{is_idempotent, HiveClient};
+use crate::Error;
+use async_trait::async_trait;
+use jsonrpsee::client_transport::ws::WsError;
+use jsonrpsee::http_client::transport::Error as HttpError;
+use rand::Rng;
+use serde_json::value::RawValue;
+use serde_json::Value;
+use std::time::Duration;
+
+/// When and how often a request that did not reach the node is sent again
+#[derive(Debug, Clone, Copy)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt, 0 to never retry
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled for every further retry
+    pub initial_backoff: Duration,
+    /// Upper bound of the backoff
+    pub max_backoff: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 3,
+            initial_backoff: Duration::from_millis(200),
+            max_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Policy that never retries
+    pub fn none() -> Self {
+        Self {
+            max_retries: 0,
+            ..Self::default()
+        }
+    }
+
+    /// Backoff before retry number `retry`, starting at 0.
+    ///
+    /// Half of the exponential backoff is random, so clients that failed at the same time do not
+    /// retry at the same time.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        let backoff = self
+            .initial_backoff
+            .saturating_mul(2u32.saturating_pow(retry))
+            .min(self.max_backoff);
+        let half = backoff / 2;
+
+        half + half.mul_f64(rand::thread_rng().gen())
+    }
+}
+
+/// Client that retries read requests whose connection failed or timed out.
+///
+/// Errors returned by the node are not retried, neither are broadcasts, see `FailoverClient`.
+pub struct RetryClient<C> {
+    client: C,
+    policy: RetryPolicy,
+}
+
+impl<C: HiveClient> RetryClient<C> {
+    /// # Arguments
+    /// * `client` - Client to send the requests with
+    /// * `policy` - How often to retry
+    pub fn new(client: C, policy: RetryPolicy) -> Self {
+        Self { client, policy }
+    }
+
+    /// The wrapped client
+    pub fn inner(&self) -> &C {
+        &self.client
+    }
+}
+
+/// Whether the request failed to reach the node or timed out, as opposed to being too large or
+/// answered with something that is not a valid response
+fn is_retryable(error: &jsonrpsee::core::Error) -> bool {
+    match error {
+        jsonrpsee::core::Error::RequestTimeout => true,
+        jsonrpsee::core::Error::Transport(e) => {
+            matches!(e.downcast_ref(), Some(HttpError::Http(_)))
+                || matches!(e.downcast_ref(), Some(WsError::Connection(_)))
+        }
+        _ => false,
+    }
+}
+
+#[async_trait]
+impl<C: HiveClient> HiveClient for RetryClient<C> {
+    async fn request_raw(
+        &self,
+        method: &str,
+        params: Option<Box<RawValue>>,
+    ) -> Result<Value, Error> {
+        let mut retry = 0;
+
+        loop {
+            match self.client.request_raw(method, params.clone()).await {
+                Err(Error::Transport(e))
+                    if retry < self.policy.max_retries
+                        && is_idempotent(method)
+                        && is_retryable(&e) =>
+                {
+                    tokio::time::sleep(self.policy.backoff(retry)).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn batch_raw(
+        &self,
+        requests: Vec<(String, Option<Box<RawValue>>)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        let idempotent = requests.iter().all(|(method, _)| is_idempotent(method));
+        let mut retry = 0;
+
+        loop {
+            match self.client.batch_raw(requests.clone()).await {
+                Err(Error::Transport(e))
+                    if retry < self.policy.max_retries && idempotent && is_retryable(&e) =>
+                {
+                    tokio::time::sleep(self.policy.backoff(retry)).await;
+                    retry += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::retry::{RetryClient, RetryPolicy};
+    use crate::client::{request, HiveClient};
+    use crate::params::EmptyObjectParams;
+    use crate::Error;
+    use async_trait::async_trait;
+    use serde_json::value::RawValue;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::time::Duration;
+
+    /// Times out on the first `failures` requests
+    struct FailingClient {
+        failures: u32,
+        requests: AtomicU32,
+    }
+
+    #[async_trait]
+    impl HiveClient for FailingClient {
+        async fn request_raw(&self, _: &str, _: Option<Box<RawValue>>) -> Result<Value, Error> {
+            if self.requests.fetch_add(1, Ordering::SeqCst) < self.failures {
+                Err(Error::Transport(jsonrpsee::core::Error::RequestTimeout))
+            } else {
+                Ok(json!("ok"))
+            }
+        }
+    }
+
+    fn retry_client(failures: u32) -> RetryClient<FailingClient> {
+        let policy = RetryPolicy {
+            max_retries: 2,
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(2),
+        };
+        let client = FailingClient {
+            failures,
+            requests: AtomicU32::new(0),
+        };
+
+        RetryClient::new(client, policy)
+    }
+
+    #[test]
+    fn exponential_backoff_with_jitter() {
+        let policy = RetryPolicy {
+            max_retries: 10,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+
+        for (retry, max) in [(0, 100), (1, 200), (2, 400), (3, 800), (4, 1000), (9, 1000)] {
+            let backoff = policy.backoff(retry);
+            assert!(backoff >= Duration::from_millis(max / 2), "{:?}", backoff);
+            assert!(backoff <= Duration::from_millis(max), "{:?}", backoff);
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_reads() {
+        let client = retry_client(2);
+
+        let result: String = request(&client, "database_api.get_version", EmptyObjectParams)
+            .await
+            .unwrap();
+
+        assert_eq!(result, "ok");
+        assert_eq!(client.inner().requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn gives_up_after_max_retries() {
+        let client = retry_client(3);
+
+        let result = request::<_, _, Value>(&client, "database_api.get_version", EmptyObjectParams);
+
+        assert!(matches!(result.await, Err(Error::Transport(_))));
+        assert_eq!(client.inner().requests.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn never_retries_broadcasts() {
+        let client = retry_client(1);
+
+        let result = request::<_, _, Value>(
+            &client,
+            "condenser_api.broadcast_transaction",
+            EmptyObjectParams,
+        );
+
+        assert!(matches!(result.await, Err(Error::Transport(_))));
+        assert_eq!(client.inner().requests.load(Ordering::SeqCst), 1);
+    }
+}
@@ -0,0 +1,606 @@
+use crate::block_api::block::Block;
+use crate::block_api::{get_block, get_block_range};
+use crate::client::{request, HiveClient};
+use crate::condenser_api::broadcast_transaction;
+use crate::condenser_api::transaction::{BlockchainMode, Transaction};
+use crate::database_api::get_dynamic_global_properties;
+use crate::enc::EncodeError;
+use crate::transaction_status_api::{find_transaction, TransactionStatus};
+use crate::Error;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Time between two blocks
+const BLOCK_INTERVAL: Duration = Duration::from_secs(3);
+
+/// JSON-RPC code of hived for methods of plugins that are not enabled
+const METHOD_NOT_FOUND: i32 = -32601;
+
+/// Where `condenser_api.broadcast_transaction_synchronous` included the transaction
+#[derive(Deserialize, Debug)]
+pub struct BroadcastResult {
+    pub id: String,
+    pub block_num: u32,
+    /// Position of the transaction in the block
+    pub trx_num: u32,
+    pub expired: bool,
+}
+
+/// Broadcasts a transaction and waits until the node included it in a block.
+///
+/// The node answers after the next block at the latest, so the request needs a timeout of more
+/// than a block interval.
+///
+/// # Arguments
+/// * `client` - Client to broadcast with
+/// * `transaction` - Signed transaction
+pub async fn broadcast_transaction_synchronous<C: HiveClient + ?Sized>(
+    client: &C,
+    transaction: &Transaction,
+) -> Result<BroadcastResult, Error> {
+    let response: BroadcastResult = request(
+        client,
+        "condenser_api.broadcast_transaction_synchronous",
+        std::slice::from_ref(transaction),
+    )
+    .await?;
+
+    Ok(response)
+}
+
+/// Outcome of `confirm_transaction`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TransactionConfirmation {
+    Included {
+        block_num: u32,
+        /// Position of the transaction in the block
+        trx_num: u32,
+        irreversible: bool,
+    },
+    /// The transaction was not included before its expiration and never will be
+    Expired,
+}
+
+#[derive(Debug)]
+pub enum ConfirmError {
+    Client(Error),
+    /// The transaction cannot be encoded to compute its id
+    Encode(EncodeError),
+}
+
+impl Display for ConfirmError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfirmError::Client(_) => f.write_str("cannot confirm transaction"),
+            ConfirmError::Encode(_) => f.write_str("cannot compute transaction id"),
+        }
+    }
+}
+
+impl std::error::Error for ConfirmError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ConfirmError::Client(e) => Some(e),
+            ConfirmError::Encode(e) => Some(e),
+        }
+    }
+}
+
+impl From<Error> for ConfirmError {
+    fn from(e: Error) -> Self {
+        ConfirmError::Client(e)
+    }
+}
+
+impl From<EncodeError> for ConfirmError {
+    fn from(e: EncodeError) -> Self {
+        ConfirmError::Encode(e)
+    }
+}
+
+/// Broadcasts a transaction and waits until it is included or expired, see `confirm_transaction`
+///
+/// # Arguments
+/// * `client` - Client to broadcast and confirm with
+/// * `transaction` - Signed transaction
+/// * `mode` - `Irreversible` to wait until the inclusion or expiration can not be undone anymore
+pub async fn broadcast_and_confirm<C: HiveClient + ?Sized>(
+    client: &C,
+    transaction: &Transaction,
+    mode: BlockchainMode,
+) -> Result<TransactionConfirmation, ConfirmError> {
+    let from = get_dynamic_global_properties(client)
+        .await?
+        .head_block_number
+        + 1;
+    broadcast_transaction(client, std::slice::from_ref(transaction)).await?;
+
+    confirm_transaction(client, transaction, from, mode).await
+}
+
+/// Waits until a broadcast transaction is included in a block or expired.
+///
+/// Asks `transaction_status_api.find_transaction` every block interval. If the node does not run
+/// that plugin, or still does not know the transaction a block interval after its expiration, the
+/// blocks starting at `from` are searched for the id of the transaction instead.
+/// With `Reversible` the result can still be undone by a fork, use `Irreversible` for transfers
+/// that need to be final.
+///
+/// # Arguments
+/// * `client` - Client to confirm with
+/// * `transaction` - Signed transaction that was broadcast
+/// * `from` - First block that can include the transaction, e.g. the block after the head block
+///   at the time of the broadcast
+/// * `mode` - `Irreversible` to wait until the inclusion or expiration can not be undone anymore
+pub async fn confirm_transaction<C: HiveClient + ?Sized>(
+    client: &C,
+    transaction: &Transaction,
+    from: u32,
+    mode: BlockchainMode,
+) -> Result<TransactionConfirmation, ConfirmError> {
+    confirm_transaction_with_interval(client, transaction, from, mode, BLOCK_INTERVAL).await
+}
+
+pub(crate) async fn confirm_transaction_with_interval<C: HiveClient + ?Sized>(
+    client: &C,
+    transaction: &Transaction,
+    from: u32,
+    mode: BlockchainMode,
+    interval: Duration,
+) -> Result<TransactionConfirmation, ConfirmError> {
+    let id = transaction.id()?;
+    let mut scan = BlockScan {
+        id: &id,
+        expiration: transaction.expiration,
+        mode,
+        next: from,
+    };
+    let mut status_api = true;
+
+    loop {
+        let confirmation = if status_api {
+            match scan.find_status(client).await {
+                Ok(Some(Status::Unavailable)) => {
+                    status_api = false;
+                    continue;
+                }
+                Ok(Some(Status::Confirmed(confirmation))) => Some(confirmation),
+                Ok(None) => None,
+                Err(Error::Rpc(e)) if e.code == METHOD_NOT_FOUND => {
+                    status_api = false;
+                    continue;
+                }
+                Err(e) => return Err(e.into()),
+            }
+        } else {
+            scan.scan_blocks(client).await?
+        };
+
+        if let Some(confirmation) = confirmation {
+            return Ok(confirmation);
+        }
+
+        tokio::time::sleep(interval).await;
+    }
+}
+
+enum Status {
+    Confirmed(TransactionConfirmation),
+    /// The node can not tell or is past the expiration without knowing, search the blocks instead
+    Unavailable,
+}
+
+struct BlockScan<'a> {
+    id: &'a str,
+    expiration: DateTime<Utc>,
+    mode: BlockchainMode,
+    /// Next block to search
+    next: u32,
+}
+
+impl BlockScan<'_> {
+    /// Asks the node where the transaction is, `None` if it is not settled in `mode` yet
+    async fn find_status<C: HiveClient + ?Sized>(
+        &self,
+        client: &C,
+    ) -> Result<Option<Status>, Error> {
+        let response = find_transaction(client, self.id, Some(self.expiration)).await?;
+        let reversible = self.mode == BlockchainMode::Reversible;
+
+        let (block_num, irreversible) = match (response.status, response.block_num) {
+            (TransactionStatus::WithinIrreversibleBlock, Some(block_num)) => (block_num, true),
+            (TransactionStatus::WithinReversibleBlock, Some(block_num)) if reversible => {
+                (block_num, false)
+            }
+            (TransactionStatus::ExpiredIrreversible, _) => {
+                return Ok(Some(Status::Confirmed(TransactionConfirmation::Expired)))
+            }
+            (TransactionStatus::ExpiredReversible, _) if reversible => {
+                return Ok(Some(Status::Confirmed(TransactionConfirmation::Expired)))
+            }
+            // The node does not track transactions back to the expiration
+            (TransactionStatus::TooOld, _) => return Ok(Some(Status::Unavailable)),
+            // A node that never received the transaction or lost track of it keeps answering
+            // this after the expiration, the blocks tell whether it was included
+            (TransactionStatus::Unknown | TransactionStatus::WithinMempool, _) => {
+                let time = get_dynamic_global_properties(client).await?.time;
+                let deadline =
+                    self.expiration + chrono::Duration::seconds(BLOCK_INTERVAL.as_secs() as i64);
+
+                return Ok(if time > deadline {
+                    Some(Status::Unavailable)
+                } else {
+                    None
+                });
+            }
+            _ => return Ok(None),
+        };
+
+        // The status does not tell the position in the block, a fork may also have dropped it
+        let block = get_block(client, block_num).await?;
+
+        Ok(trx_num(&block, self.id).map(|trx_num| {
+            Status::Confirmed(TransactionConfirmation::Included {
+                block_num,
+                trx_num,
+                irreversible,
+            })
+        }))
+    }
+
+    /// Searches the blocks produced since the last call, `None` if the transaction is in none
+    /// of them and the last one is before the expiration
+    async fn scan_blocks<C: HiveClient + ?Sized>(
+        &mut self,
+        client: &C,
+    ) -> Result<Option<TransactionConfirmation>, Error> {
+        let properties = get_dynamic_global_properties(client).await?;
+        let irreversible = properties.last_irreversible_block_num;
+        let last = match self.mode {
+            BlockchainMode::Reversible => properties.head_block_number,
+            BlockchainMode::Irreversible => irreversible,
+        };
+
+        for block in get_block_range(client, self.next..last.saturating_add(1)).await? {
+            let block_num = self.next;
+            self.next += 1;
+
+            if let Some(trx_num) = trx_num(&block, self.id) {
+                return Ok(Some(TransactionConfirmation::Included {
+                    block_num,
+                    trx_num,
+                    irreversible: block_num <= irreversible,
+                }));
+            }
+            // No block after the expiration can include the transaction
+            if block.timestamp > self.expiration {
+                return Ok(Some(TransactionConfirmation::Expired));
+            }
+        }
+
+        Ok(None)
+    }
+}
+
+fn trx_num(block: &Block, id: &str) -> Option<u32> {
+    block
+        .transaction_ids
+        .iter()
+        .position(|transaction_id| transaction_id == id)
+        .map(|position| position as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::tests::block_json;
+    use crate::client::MockClient;
+    use crate::condenser_api::broadcast::{
+        broadcast_and_confirm, broadcast_transaction_synchronous,
+        confirm_transaction_with_interval, TransactionConfirmation,
+    };
+    use crate::condenser_api::transaction::{BlockchainMode, Transaction};
+    use crate::crypto::private_key::PrivateKey;
+    use crate::database_api::tests::dynamic_global_properties_json;
+    use crate::signer::tests::unsigned_transaction;
+    use chrono::{DateTime, TimeZone, Utc};
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    /// Transaction expiring at 10:10, after the blocks of `block_json` at 10:00
+    fn transaction() -> Transaction {
+        let mut transaction = unsigned_transaction();
+        transaction.expiration = Utc.with_ymd_and_hms(2022, 10, 20, 10, 10, 0).unwrap();
+
+        transaction
+            .sign(&PrivateKey::from_seed("alice").unwrap(), None)
+            .unwrap()
+    }
+
+    /// Time of block `n`, block 100 is produced at 10:00
+    fn block_time(n: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2022, 10, 20, 10, 0, 0).unwrap()
+            + chrono::Duration::seconds(3 * (n as i64 - 100))
+    }
+
+    /// Block `n` with `id` as its second transaction if `includes` is true
+    fn block(n: u32, id: &str, includes: bool) -> Value {
+        let mut block = block_json(n, 0, 0);
+        block["timestamp"] = json!(block_time(n).format("%Y-%m-%dT%H:%M:%S").to_string());
+        block["transaction_ids"] = match includes {
+            true => json!(["00", id]),
+            false => json!(["00"]),
+        };
+
+        block
+    }
+
+    /// Node whose head block advances by one on every `get_dynamic_global_properties`, with the
+    /// transaction in block `included`
+    fn chain(head: u32, included: u32, id: &str) -> MockClient {
+        let client = MockClient::new();
+        let head = Arc::new(AtomicU32::new(head));
+
+        let range_head = head.clone();
+        let range_id = id.to_owned();
+        client.respond_with("block_api.get_block_range", move |params| {
+            let start = params["starting_block_num"].as_u64().unwrap() as u32;
+            let count = params["count"].as_u64().unwrap() as u32;
+            let blocks: Vec<_> = (start..start + count)
+                .take_while(|&n| n <= range_head.load(Ordering::SeqCst))
+                .map(|n| block(n, &range_id, n == included))
+                .collect();
+            Ok(json!({ "blocks": blocks }))
+        });
+        let block_id = id.to_owned();
+        client.respond_with("block_api.get_block", move |params| {
+            let n = params["block_num"].as_u64().unwrap() as u32;
+            Ok(json!({ "block": block(n, &block_id, n == included) }))
+        });
+        client.respond_with("database_api.get_dynamic_global_properties", move |_| {
+            let head = head.fetch_add(1, Ordering::SeqCst);
+            Ok(dynamic_global_properties_json(
+                head,
+                head - 5,
+                block_time(head),
+            ))
+        });
+
+        client
+    }
+
+    #[tokio::test]
+    async fn broadcast_synchronous() {
+        let transaction = transaction();
+        let id = transaction.id().unwrap();
+        let client = MockClient::new();
+        client.respond(
+            "condenser_api.broadcast_transaction_synchronous",
+            json!({ "id": id, "block_num": 101, "trx_num": 3, "expired": false }),
+        );
+
+        let result = broadcast_transaction_synchronous(&client, &transaction)
+            .await
+            .unwrap();
+
+        assert_eq!(result.id, id);
+        assert_eq!((result.block_num, result.trx_num), (101, 3));
+        assert!(!result.expired);
+        let params = &client.requests()[0].1;
+        assert_eq!(params[0]["ref_block_prefix"], json!(2));
+    }
+
+    #[tokio::test]
+    async fn broadcast_and_wait() {
+        let transaction = transaction();
+        let id = transaction.id().unwrap();
+        let client = chain(100, 101, &id);
+        client.respond("condenser_api.broadcast_transaction", json!({}));
+        client.respond(
+            "transaction_status_api.find_transaction",
+            json!({ "status": "within_reversible_block", "block_num": 101 }),
+        );
+
+        let confirmation = broadcast_and_confirm(&client, &transaction, BlockchainMode::Reversible)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            confirmation,
+            TransactionConfirmation::Included {
+                block_num: 101,
+                trx_num: 1,
+                irreversible: false
+            }
+        );
+        let methods: Vec<_> = client
+            .requests()
+            .into_iter()
+            .map(|(method, _)| method)
+            .collect();
+        assert_eq!(
+            methods,
+            [
+                "database_api.get_dynamic_global_properties",
+                "condenser_api.broadcast_transaction",
+                "transaction_status_api.find_transaction",
+                "block_api.get_block"
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn confirm_with_status_api() {
+        let transaction = transaction();
+        let id = transaction.id().unwrap();
+        let client = chain(100, 101, &id);
+        let polls = AtomicU32::new(0);
+        client.respond_with("transaction_status_api.find_transaction", move |_| {
+            Ok(match polls.fetch_add(1, Ordering::SeqCst) {
+                0 => json!({ "status": "within_mempool" }),
+                1 => json!({ "status": "within_reversible_block", "block_num": 101 }),
+                _ => json!({ "status": "within_irreversible_block", "block_num": 101 }),
+            })
+        });
+
+        let confirmation = confirm_transaction_with_interval(
+            &client,
+            &transaction,
+            101,
+            BlockchainMode::Irreversible,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            confirmation,
+            TransactionConfirmation::Included {
+                block_num: 101,
+                trx_num: 1,
+                irreversible: true
+            }
+        );
+        let finds = client
+            .requests()
+            .iter()
+            .filter(|(method, _)| method == "transaction_status_api.find_transaction")
+            .count();
+        assert_eq!(finds, 3);
+    }
+
+    #[tokio::test]
+    async fn confirm_by_scanning_blocks() {
+        let transaction = transaction();
+        let id = transaction.id().unwrap();
+
+        // Without `transaction_status_api` the blocks are searched
+        let client = chain(100, 103, &id);
+        let confirmation = confirm_transaction_with_interval(
+            &client,
+            &transaction,
+            99,
+            BlockchainMode::Reversible,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            confirmation,
+            TransactionConfirmation::Included {
+                block_num: 103,
+                trx_num: 1,
+                irreversible: false
+            }
+        );
+
+        let client = chain(100, 103, &id);
+        let confirmation = confirm_transaction_with_interval(
+            &client,
+            &transaction,
+            99,
+            BlockchainMode::Irreversible,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            confirmation,
+            TransactionConfirmation::Included {
+                block_num: 103,
+                trx_num: 1,
+                irreversible: true
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn expired() {
+        let mut transaction = transaction();
+        transaction.expiration = Utc.with_ymd_and_hms(2022, 10, 20, 9, 0, 0).unwrap();
+        let id = transaction.id().unwrap();
+
+        let client = chain(100, 0, &id);
+        let confirmation = confirm_transaction_with_interval(
+            &client,
+            &transaction,
+            101,
+            BlockchainMode::Reversible,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+        assert_eq!(confirmation, TransactionConfirmation::Expired);
+
+        let client = chain(100, 0, &id);
+        client.respond(
+            "transaction_status_api.find_transaction",
+            json!({ "status": "expired_irreversible" }),
+        );
+        let confirmation = confirm_transaction_with_interval(
+            &client,
+            &transaction,
+            101,
+            BlockchainMode::Irreversible,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+        assert_eq!(confirmation, TransactionConfirmation::Expired);
+    }
+
+    #[tokio::test]
+    async fn stop_asking_after_expiration() {
+        // Expires with block 102, the blocks are searched once the head is past block 103
+        let mut transaction = transaction();
+        transaction.expiration = block_time(102);
+        let id = transaction.id().unwrap();
+
+        let client = chain(100, 0, &id);
+        client.respond(
+            "transaction_status_api.find_transaction",
+            json!({ "status": "unknown" }),
+        );
+        let confirmation = confirm_transaction_with_interval(
+            &client,
+            &transaction,
+            101,
+            BlockchainMode::Reversible,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+        assert_eq!(confirmation, TransactionConfirmation::Expired);
+        assert!(client
+            .requests()
+            .iter()
+            .any(|(method, _)| method == "block_api.get_block_range"));
+
+        // The node lost track of a transaction that was included
+        let client = chain(100, 102, &id);
+        client.respond(
+            "transaction_status_api.find_transaction",
+            json!({ "status": "within_mempool" }),
+        );
+        let confirmation = confirm_transaction_with_interval(
+            &client,
+            &transaction,
+            101,
+            BlockchainMode::Reversible,
+            Duration::ZERO,
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            confirmation,
+            TransactionConfirmation::Included {
+                block_num: 102,
+                trx_num: 1,
+                irreversible: false
+            }
+        );
+    }
+}
@@ -1,29 +1,34 @@
+mod broadcast;
 pub mod operation;
 pub mod transaction;
+mod transaction_builder;
 mod version;
 
+use crate::client::{request, HiveClient};
 use crate::params::EmptyArrayParams;
-use jsonrpsee::core::client::ClientT;
-use jsonrpsee::http_client::HttpClient;
+use crate::Error;
 use serde_json::Value;
 use transaction::Transaction;
+
+pub use broadcast::{
+    broadcast_and_confirm, broadcast_transaction_synchronous, confirm_transaction, BroadcastResult,
+    ConfirmError, TransactionConfirmation,
+};
+pub use transaction_builder::{TransactionBuildError, TransactionBuilder};
 pub use version::Version;
 
-pub async fn broadcast_transaction(
-    client: &HttpClient,
+pub async fn broadcast_transaction<C: HiveClient + ?Sized>(
+    client: &C,
     transactions: &[Transaction],
-) -> Result<Value, Box<dyn std::error::Error + Send + Sync>> {
-    let response: Value = client
-        .request("condenser_api.broadcast_transaction", transactions)
-        .await?;
+) -> Result<Value, Error> {
+    let response: Value =
+        request(client, "condenser_api.broadcast_transaction", transactions).await?;
 
     Ok(response)
 }
 
-pub async fn get_version(client: &HttpClient) -> anyhow::Result<Version> {
-    let response: Version = client
-        .request("condenser_api.get_version", EmptyArrayParams)
-        .await?;
+pub async fn get_version<C: HiveClient + ?Sized>(client: &C) -> Result<Version, Error> {
+    let response: Version = request(client, "condenser_api.get_version", EmptyArrayParams).await?;
 
     Ok(response)
 }
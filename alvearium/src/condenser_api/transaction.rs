@@ -1,13 +1,17 @@
 use crate::condenser_api::operation::Operation;
 use crate::crypto::private_key::PrivateKey;
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{sha256, signature_to_bytes};
 use crate::database_api::TxSignProperties;
-use crate::enc::encode_to_vec;
+use crate::enc::{encode_to_vec, EncodeError};
 use crate::ser::serialize_hive_time;
+use crate::signer::{Signer, SignerError};
+use crate::{HiveEncode, HiveEncoder};
 use alvearium_derive::HiveEncode;
 use chrono::{DateTime, Duration, Utc};
 use hex_literal::hex;
 use serde::Serialize;
-use std::num::ParseIntError;
+use std::fmt::{Display, Formatter};
 
 const DEFAULT_CHAIN_ID: [u8; 32] = hex!(
     "beeab0de" "00000000"
@@ -22,41 +26,70 @@ pub enum BlockchainMode {
     Irreversible,
 }
 
+/// Longest time a transaction may be valid for after the head block
+pub const MAX_TIME_UNTIL_EXPIRATION: i64 = 60 * 60;
+
+/// Longest time a transaction that collects signatures of multiple parties may be valid for
+pub const MAX_TIME_UNTIL_MULTISIG_EXPIRATION: i64 = 24 * 60 * 60;
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum TransactionCreateError {
-    PrefixDecodeError(DecodeHexError),
+    /// The block referenced for TAPoS does not have a 20 byte hex id
+    InvalidBlockId(String),
+    NoOperations,
+    /// The expiration is not in the future or further than `max` away
+    InvalidExpiration {
+        expiration: Duration,
+        max: Duration,
+    },
+    /// An operation cannot be encoded, e.g. because of an uncompressed public key
+    Encode(EncodeError),
 }
 
-impl From<DecodeHexError> for TransactionCreateError {
-    fn from(e: DecodeHexError) -> Self {
-        TransactionCreateError::PrefixDecodeError(e)
+impl Display for TransactionCreateError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionCreateError::InvalidBlockId(id) => write!(f, "invalid block id {}", id),
+            TransactionCreateError::NoOperations => f.write_str("transaction has no operations"),
+            TransactionCreateError::InvalidExpiration { expiration, max } => write!(
+                f,
+                "expiration of {}s is not between 1s and {}s",
+                expiration.num_seconds(),
+                max.num_seconds()
+            ),
+            TransactionCreateError::Encode(_) => f.write_str("cannot encode transaction"),
+        }
     }
 }
 
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub enum DecodeHexError {
-    OddLength,
-    ParseInt(ParseIntError),
+impl std::error::Error for TransactionCreateError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TransactionCreateError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
 }
 
-impl From<ParseIntError> for DecodeHexError {
-    fn from(e: ParseIntError) -> Self {
-        DecodeHexError::ParseInt(e)
+impl From<EncodeError> for TransactionCreateError {
+    fn from(e: EncodeError) -> Self {
+        TransactionCreateError::Encode(e)
     }
 }
 
-/*
-https://stackoverflow.com/questions/52987181/how-can-i-convert-a-hex-string-to-a-u8-slice by Sven Marnach
- */
-fn decode_hex(s: &str) -> Result<Vec<u8>, DecodeHexError> {
-    if s.len() % 2 != 0 {
-        Err(DecodeHexError::OddLength)
-    } else {
-        (0..s.len())
-            .step_by(2)
-            .map(|i| u8::from_str_radix(&s[i..i + 2], 16).map_err(|e| e.into()))
-            .collect()
+/// Returns the TAPoS `ref_block_prefix` of a block, the little endian bytes 4 to 8 of its id
+///
+/// # Arguments
+/// * `block_id` - Hex encoded id of the referenced block
+pub fn ref_block_prefix(block_id: &str) -> Result<u32, TransactionCreateError> {
+    let invalid = || TransactionCreateError::InvalidBlockId(block_id.to_owned());
+    let id = hex::decode(block_id).map_err(|_| invalid())?;
+
+    if id.len() != 20 {
+        return Err(invalid());
     }
+
+    Ok(u32::from_le_bytes([id[4], id[5], id[6], id[7]]))
 }
 
 impl UnsignedTransaction {
@@ -64,34 +97,78 @@ impl UnsignedTransaction {
         properties: &TxSignProperties,
         operations: Vec<Operation>,
     ) -> Result<Self, TransactionCreateError> {
-        let ref_block_num = properties.ref_block_num;
-        let prefix =
-            decode_hex(&properties.ref_block_prefix).map_err(TransactionCreateError::from)?;
-        let mut prefix_bytes: [u8; 4] = Default::default();
-        prefix_bytes.copy_from_slice(&prefix[4..8]);
-        let ref_block_prefix = u32::from_le_bytes(prefix_bytes);
+        let ref_block_prefix = ref_block_prefix(&properties.ref_block_prefix)?;
 
         Ok(UnsignedTransaction {
             operations,
-            ref_block_num: ref_block_num as u16,
+            // TAPoS references the lower 16 bits of the block number
+            ref_block_num: properties.ref_block_num as u16,
             ref_block_prefix,
             expiration: properties.time + Duration::minutes(10),
             extensions: vec![],
         })
     }
 
-    pub fn sign(self, key: &PrivateKey, chain_id: Option<[u8; 32]>) -> Transaction {
-        let v = encode_to_vec(&self).unwrap();
-        let signature =
-            key.sign_ecdsa_canonical([chain_id.unwrap_or(DEFAULT_CHAIN_ID).as_ref(), &v].concat());
-        let (recovery_id, buf) = signature.serialize_compact();
-        let mut buffer: [u8; 65] = [0; 65];
+    /// Signs the transaction, fails if it cannot be encoded, e.g. because an operation contains an
+    /// uncompressed public key
+    pub fn sign(
+        self,
+        key: &PrivateKey,
+        chain_id: Option<[u8; 32]>,
+    ) -> Result<Transaction, EncodeError> {
+        self.sign_with_keys(&[key], chain_id)
+    }
+
+    /// Signs the transaction with every key in `keys`, for operations that need the authority of
+    /// multiple keys or accounts.
+    pub fn sign_with_keys(
+        self,
+        keys: &[&PrivateKey],
+        chain_id: Option<[u8; 32]>,
+    ) -> Result<Transaction, EncodeError> {
+        let v = encode_to_vec(&self)?;
+        let message = [chain_id.unwrap_or(DEFAULT_CHAIN_ID).as_ref(), &v].concat();
+        let signatures = keys
+            .iter()
+            .map(|key| hex::encode(signature_to_bytes(&key.sign_ecdsa_canonical(&message))))
+            .collect();
+
+        Ok(self.into_transaction(signatures))
+    }
+
+    /// Signs the transaction using `signer`, with the keys belonging to `public_keys`
+    ///
+    /// # Arguments
+    /// * `signer` - Signer holding the private keys
+    /// * `public_keys` - Keys to sign with, the signer needs to be able to sign for all of them
+    /// * `chain_id` - Chain to sign for, mainnet if `None`
+    pub async fn sign_with_signer<S: Signer + ?Sized>(
+        self,
+        signer: &S,
+        public_keys: &[PublicKey],
+        chain_id: Option<[u8; 32]>,
+    ) -> Result<Transaction, SignerError> {
+        let digest = self.digest(chain_id)?;
+        let mut signatures = vec![];
+
+        for public_key in public_keys {
+            let signature = signer.sign_digest(digest, public_key).await?;
+            signatures.push(hex::encode(signature_to_bytes(&signature)));
+        }
+
+        Ok(self.into_transaction(signatures))
+    }
+
+    /// The digest that is signed, the SHA-256 of the chain id followed by the encoded transaction
+    pub fn digest(&self, chain_id: Option<[u8; 32]>) -> Result<[u8; 32], EncodeError> {
+        let v = encode_to_vec(self)?;
 
-        // I guess recovery_id can never be larger than a u8 the code says it needs to be 0..=3
-        buffer[0] = recovery_id.to_i32() as u8 + 31;
-        buffer[1..].clone_from_slice(&buf);
-        let signatures = vec![hex::encode(buffer)];
+        Ok(sha256(
+            [chain_id.unwrap_or(DEFAULT_CHAIN_ID).as_ref(), &v].concat(),
+        ))
+    }
 
+    fn into_transaction(self, signatures: Vec<String>) -> Transaction {
         Transaction {
             ref_block_num: self.ref_block_num,
             ref_block_prefix: self.ref_block_prefix,
@@ -117,8 +194,23 @@ pub struct Transaction {
     pub extensions: Vec<()>,
 }
 
-#[derive(Serialize, Debug, HiveEncode)]
-#[hive_encode(crate = "crate")]
+impl Transaction {
+    /// Id of the transaction, the first 20 bytes of the SHA-256 of the transaction without its
+    /// signatures
+    pub fn id(&self) -> Result<String, EncodeError> {
+        let v = encode_to_vec(UnsignedView {
+            ref_block_num: self.ref_block_num,
+            ref_block_prefix: self.ref_block_prefix,
+            expiration: self.expiration,
+            operations: &self.operations,
+            extensions: &self.extensions,
+        })?;
+
+        Ok(hex::encode(&sha256(v)[..20]))
+    }
+}
+
+#[derive(Serialize, Debug)]
 pub struct UnsignedTransaction {
     pub ref_block_num: u16,
     pub ref_block_prefix: u32,
@@ -130,3 +222,137 @@ pub struct UnsignedTransaction {
     pub operations: Vec<Operation>,
     pub extensions: Vec<()>,
 }
+
+impl HiveEncode for UnsignedTransaction {
+    fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        UnsignedView {
+            ref_block_num: self.ref_block_num,
+            ref_block_prefix: self.ref_block_prefix,
+            expiration: self.expiration,
+            operations: &self.operations,
+            extensions: &self.extensions,
+        }
+        .encode(encoder)
+    }
+}
+
+/// Encodes a transaction without its signatures, what is signed and hashed for the id
+#[derive(HiveEncode)]
+#[hive_encode(crate = "crate")]
+struct UnsignedView<'a> {
+    ref_block_num: u16,
+    ref_block_prefix: u32,
+    expiration: DateTime<Utc>,
+    operations: &'a Vec<Operation>,
+    extensions: &'a Vec<()>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::condenser_api::operation::Operation;
+    use crate::condenser_api::transaction::{
+        ref_block_prefix, TransactionCreateError, UnsignedTransaction,
+    };
+    use crate::crypto::key_set::{KeySet, PublicKeySet};
+    use crate::crypto::private_key::PrivateKey;
+    use crate::crypto::public_key::PublicKey;
+    use crate::crypto::{sha256, FromWif};
+    use crate::database_api::TxSignProperties;
+    use crate::enc::{encode_to_vec, EncodeError};
+    use crate::operation::{AccountCreate, Asset, AssetSymbol};
+    use crate::signer::tests::unsigned_transaction;
+    use chrono::{Duration, Utc};
+    use std::error::Error;
+
+    #[test]
+    fn tapos_from_block_id() {
+        assert_eq!(
+            ref_block_prefix("0000000109833ce528d5bbfb3f6225b39ee10086"),
+            Ok(3_845_948_169)
+        );
+
+        for id in [
+            "",
+            "00000001",
+            "0000000109833ce528d5bbfb3f6225b39ee1008",
+            "é0000001",
+        ] {
+            assert_eq!(
+                ref_block_prefix(id),
+                Err(TransactionCreateError::InvalidBlockId(id.to_owned()))
+            );
+        }
+    }
+
+    #[test]
+    fn new_transaction() {
+        let time = Utc::now();
+        let properties = TxSignProperties {
+            time,
+            ref_block_num: 65_537,
+            ref_block_prefix: "0001000109833ce528d5bbfb3f6225b39ee10086".to_owned(),
+        };
+
+        let transaction = UnsignedTransaction::new(&properties, vec![]).unwrap();
+        assert_eq!(transaction.ref_block_num, 1);
+        assert_eq!(transaction.ref_block_prefix, 3_845_948_169);
+        assert_eq!(transaction.expiration, time + Duration::minutes(10));
+
+        let properties = TxSignProperties {
+            ref_block_prefix: "0001".to_owned(),
+            ..properties
+        };
+        assert!(matches!(
+            UnsignedTransaction::new(&properties, vec![]),
+            Err(TransactionCreateError::InvalidBlockId(_))
+        ));
+    }
+
+    #[test]
+    fn transaction_id() {
+        let transaction = unsigned_transaction();
+        let expected = hex::encode(&sha256(encode_to_vec(&transaction).unwrap())[..20]);
+
+        let signed = transaction
+            .sign(&PrivateKey::from_seed("alice").unwrap(), None)
+            .unwrap();
+
+        assert_eq!(signed.id().unwrap(), expected);
+    }
+
+    #[test]
+    fn sign_unencodable_transaction() {
+        let key = PublicKey::from_wif("ABC3nJ4XSefGZrjiWxsUSQmAJFYhuxJ8kSCvkCaKngUYBbiqpmWywaxmdgTcWbPio55q7CHDTNBK9mhuK9fbHg6nAKXpZCb43").unwrap();
+        let keys = PublicKeySet {
+            memo: key,
+            ..KeySet::from_password("bob", "password")
+                .unwrap()
+                .create_public(None)
+        };
+        let mut transaction = unsigned_transaction();
+        transaction.operations = vec![Operation::AccountCreate(AccountCreate::new(
+            Asset::new(3000, AssetSymbol::HIVE),
+            "alice".to_owned(),
+            "bob".to_owned(),
+            keys,
+            "{}".to_owned(),
+        ))];
+
+        assert_eq!(
+            transaction.digest(None),
+            Err(EncodeError::UncompressedPublicKey)
+        );
+        assert!(matches!(
+            transaction.sign(&PrivateKey::from_seed("alice").unwrap(), None),
+            Err(EncodeError::UncompressedPublicKey)
+        ));
+
+        // Wrappers name what failed and keep the reason as their source
+        let e = TransactionCreateError::from(EncodeError::UncompressedPublicKey);
+        assert_eq!(e.to_string(), "cannot encode transaction");
+        assert_eq!(
+            e.source().unwrap().to_string(),
+            "uncompressed public keys cannot be encoded"
+        );
+    }
+}
@@ -0,0 +1,372 @@
+use crate::client::HiveClient;
+use crate::condenser_api::operation::Operation;
+use crate::condenser_api::transaction::{
+    ref_block_prefix, BlockchainMode, Transaction, TransactionCreateError, UnsignedTransaction,
+    MAX_TIME_UNTIL_EXPIRATION, MAX_TIME_UNTIL_MULTISIG_EXPIRATION,
+};
+use crate::crypto::public_key::PublicKey;
+use crate::database_api::{get_tx_sign_properties, TxSignProperties};
+use crate::enc::encode_to_vec;
+use crate::signer::{Signer, SignerError};
+use chrono::Duration;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum TransactionBuildError {
+    /// The TAPoS block could not be fetched
+    Client(crate::Error),
+    Create(TransactionCreateError),
+    Signer(SignerError),
+}
+
+impl Display for TransactionBuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionBuildError::Client(_) => f.write_str("cannot fetch TAPoS block"),
+            TransactionBuildError::Create(_) => f.write_str("cannot create transaction"),
+            TransactionBuildError::Signer(_) => f.write_str("cannot sign transaction"),
+        }
+    }
+}
+
+impl std::error::Error for TransactionBuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TransactionBuildError::Client(e) => Some(e),
+            TransactionBuildError::Create(e) => Some(e),
+            TransactionBuildError::Signer(e) => Some(e),
+        }
+    }
+}
+
+impl From<crate::Error> for TransactionBuildError {
+    fn from(e: crate::Error) -> Self {
+        TransactionBuildError::Client(e)
+    }
+}
+
+impl From<TransactionCreateError> for TransactionBuildError {
+    fn from(e: TransactionCreateError) -> Self {
+        TransactionBuildError::Create(e)
+    }
+}
+
+impl From<SignerError> for TransactionBuildError {
+    fn from(e: SignerError) -> Self {
+        TransactionBuildError::Signer(e)
+    }
+}
+
+/// Builds a transaction that references a recent block (TAPoS) fetched from the node.
+///
+/// The transaction expires 10 minutes after the referenced head block time by default. The
+/// expiration can be raised up to 1 hour, or up to 24 hours for multisig transactions that need
+/// time to collect their signatures.
+///
+/// ```no_run
+/// # async fn example(client: &alvearium::HttpClient) {
+/// use alvearium::condenser_api::operation::Operation;
+/// use alvearium::condenser_api::TransactionBuilder;
+/// use alvearium::crypto::private_key::PrivateKey;
+/// use alvearium::operation::CustomJson;
+/// use chrono::Duration;
+///
+/// let key = PrivateKey::from_seed("alice").unwrap();
+/// let follow = CustomJson {
+///     required_auths: vec![],
+///     required_posting_auths: vec!["alice".to_owned()],
+///     id: "follow".to_owned(),
+///     json: r#"["follow",{"follower":"alice","following":"bob","what":["blog"]}]"#.to_owned(),
+/// };
+/// let transaction = TransactionBuilder::new()
+///     .operation(Operation::CustomJson(follow))
+///     .expiration(Duration::minutes(30))
+///     .sign(client, &key, &[key.create_public(None)])
+///     .await;
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TransactionBuilder {
+    operations: Vec<Operation>,
+    expiration: Duration,
+    multisig: bool,
+    mode: BlockchainMode,
+    chain_id: Option<[u8; 32]>,
+}
+
+impl Default for TransactionBuilder {
+    fn default() -> Self {
+        Self {
+            operations: vec![],
+            expiration: Duration::minutes(10),
+            multisig: false,
+            mode: BlockchainMode::Reversible,
+            chain_id: None,
+        }
+    }
+}
+
+impl TransactionBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds an operation after those added before
+    pub fn operation(mut self, operation: Operation) -> Self {
+        self.operations.push(operation);
+        self
+    }
+
+    /// Adds operations after those added before
+    pub fn operations(mut self, operations: impl IntoIterator<Item = Operation>) -> Self {
+        self.operations.extend(operations);
+        self
+    }
+
+    /// Sets how long after the time of the referenced block the transaction expires
+    pub fn expiration(mut self, expiration: Duration) -> Self {
+        self.expiration = expiration;
+        self
+    }
+
+    /// Allows an expiration of up to 24 hours, for transactions that are signed by several parties
+    pub fn multisig(mut self) -> Self {
+        self.multisig = true;
+        self
+    }
+
+    /// Sets the block that is referenced, the head block by default. Referencing the last
+    /// irreversible block keeps the transaction valid if the head block is dropped by a fork.
+    pub fn mode(mut self, mode: BlockchainMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Sets the chain to sign for, mainnet by default
+    pub fn chain_id(mut self, chain_id: [u8; 32]) -> Self {
+        self.chain_id = Some(chain_id);
+        self
+    }
+
+    /// Longest expiration that is accepted
+    pub fn max_expiration(&self) -> Duration {
+        Duration::seconds(if self.multisig {
+            MAX_TIME_UNTIL_MULTISIG_EXPIRATION
+        } else {
+            MAX_TIME_UNTIL_EXPIRATION
+        })
+    }
+
+    /// Checks that there are operations and the expiration is within the limit of the chain
+    pub fn validate(&self) -> Result<(), TransactionCreateError> {
+        if self.operations.is_empty() {
+            return Err(TransactionCreateError::NoOperations);
+        }
+
+        let max = self.max_expiration();
+        if self.expiration <= Duration::zero() || self.expiration > max {
+            return Err(TransactionCreateError::InvalidExpiration {
+                expiration: self.expiration,
+                max,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Builds the transaction without fetching anything from a node
+    ///
+    /// # Arguments
+    /// * `properties` - Block to reference and the time the expiration starts from
+    pub fn build_with_properties(
+        self,
+        properties: &TxSignProperties,
+    ) -> Result<UnsignedTransaction, TransactionCreateError> {
+        self.validate()?;
+
+        let transaction = UnsignedTransaction {
+            operations: self.operations,
+            // TAPoS references the lower 16 bits of the block number
+            ref_block_num: properties.ref_block_num as u16,
+            ref_block_prefix: ref_block_prefix(&properties.ref_block_prefix)?,
+            expiration: properties.time + self.expiration,
+            extensions: vec![],
+        };
+
+        // Signing requires the transaction to be encodable, fail here rather than there
+        encode_to_vec(&transaction)?;
+
+        Ok(transaction)
+    }
+
+    /// Fetches the block to reference and builds the transaction
+    ///
+    /// # Arguments
+    /// * `client` - Node to fetch the TAPoS block from
+    pub async fn build<C: HiveClient + ?Sized>(
+        self,
+        client: &C,
+    ) -> Result<UnsignedTransaction, TransactionBuildError> {
+        // Fail before making requests
+        self.validate()?;
+
+        let properties = get_tx_sign_properties(client, self.mode.clone()).await?;
+
+        Ok(self.build_with_properties(&properties)?)
+    }
+
+    /// Builds the transaction and signs it
+    ///
+    /// # Arguments
+    /// * `client` - Node to fetch the TAPoS block from
+    /// * `signer` - Signer holding the private keys
+    /// * `public_keys` - Keys to sign with, the signer needs to be able to sign for all of them
+    pub async fn sign<C: HiveClient + ?Sized, S: Signer + ?Sized>(
+        self,
+        client: &C,
+        signer: &S,
+        public_keys: &[PublicKey],
+    ) -> Result<Transaction, TransactionBuildError> {
+        let chain_id = self.chain_id;
+        let transaction = self.build(client).await?;
+
+        Ok(transaction
+            .sign_with_signer(signer, public_keys, chain_id)
+            .await?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::block_api::tests::{block_id, block_json};
+    use crate::client::MockClient;
+    use crate::condenser_api::operation::Operation;
+    use crate::condenser_api::transaction::{BlockchainMode, TransactionCreateError};
+    use crate::condenser_api::transaction_builder::{TransactionBuildError, TransactionBuilder};
+    use crate::crypto::private_key::PrivateKey;
+    use crate::database_api::tests::dynamic_global_properties_json;
+    use crate::database_api::TxSignProperties;
+    use crate::operation::CustomJson;
+    use chrono::{Duration, TimeZone, Utc};
+    use serde_json::json;
+
+    fn custom_json() -> Operation {
+        Operation::CustomJson(CustomJson {
+            required_auths: vec![],
+            required_posting_auths: vec!["alice".to_owned()],
+            id: "alvearium".to_owned(),
+            json: "{}".to_owned(),
+        })
+    }
+
+    fn client() -> MockClient {
+        let client = MockClient::new();
+        let time = Utc.with_ymd_and_hms(2022, 10, 20, 10, 0, 0).unwrap();
+        // The prefix is taken from bytes 4 to 8 of the block id
+        let mut properties = dynamic_global_properties_json(70_000, 69_980, time);
+        properties["head_block_id"] = json!(format!("{:08x}01020304{:024x}", 70_000, 0));
+        client.respond("database_api.get_dynamic_global_properties", properties);
+        client.respond_with("block_api.get_block", |params| {
+            let n = params["block_num"].as_u64().unwrap() as u32;
+            let mut block = block_json(n, 7, 7);
+            block["block_id"] = json!(format!("{:08x}0a0b0c0d{:024x}", n, 7));
+            Ok(json!({ "block": block }))
+        });
+
+        client
+    }
+
+    #[tokio::test]
+    async fn build_with_tapos() {
+        let client = client();
+
+        let transaction = TransactionBuilder::new()
+            .operation(custom_json())
+            .operations(vec![custom_json()])
+            .expiration(Duration::hours(1))
+            .build(&client)
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.operations.len(), 2);
+        assert_eq!(transaction.ref_block_num, (70_000 % 65_536) as u16);
+        assert_eq!(transaction.ref_block_prefix, 0x04030201);
+        assert_eq!(
+            transaction.expiration,
+            Utc.with_ymd_and_hms(2022, 10, 20, 11, 0, 0).unwrap()
+        );
+
+        let transaction = TransactionBuilder::new()
+            .operation(custom_json())
+            .mode(BlockchainMode::Irreversible)
+            .build(&client)
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.ref_block_num, (69_980 % 65_536) as u16);
+        assert_eq!(transaction.ref_block_prefix, 0x0d0c0b0a);
+        assert_eq!(
+            client.requests().last().unwrap().1,
+            json!({ "block_num": 69_980 })
+        );
+    }
+
+    #[tokio::test]
+    async fn sign() {
+        let key = PrivateKey::from_seed("alice").unwrap();
+
+        let transaction = TransactionBuilder::new()
+            .operation(custom_json())
+            .sign(&client(), &key, &[key.create_public(None)])
+            .await
+            .unwrap();
+
+        assert_eq!(transaction.signatures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn reject_invalid_transactions() {
+        let client = client();
+
+        assert!(matches!(
+            TransactionBuilder::new().build(&client).await,
+            Err(TransactionBuildError::Create(
+                TransactionCreateError::NoOperations
+            ))
+        ));
+        // Nothing is fetched for a transaction that cannot be valid
+        assert!(client.requests().is_empty());
+
+        let builder = |expiration| {
+            TransactionBuilder::new()
+                .operation(custom_json())
+                .expiration(expiration)
+        };
+        assert_eq!(
+            builder(Duration::hours(2)).validate(),
+            Err(TransactionCreateError::InvalidExpiration {
+                expiration: Duration::hours(2),
+                max: Duration::hours(1)
+            })
+        );
+        assert!(builder(Duration::hours(2)).multisig().validate().is_ok());
+        assert!(builder(Duration::hours(25)).multisig().validate().is_err());
+        assert!(builder(Duration::zero()).validate().is_err());
+    }
+
+    #[test]
+    fn malformed_block_id() {
+        let properties = TxSignProperties {
+            time: Utc::now(),
+            ref_block_num: 1,
+            ref_block_prefix: block_id(1, 0)[..10].to_owned(),
+        };
+
+        assert!(matches!(
+            TransactionBuilder::new()
+                .operation(custom_json())
+                .build_with_properties(&properties),
+            Err(TransactionCreateError::InvalidBlockId(_))
+        ));
+    }
+}
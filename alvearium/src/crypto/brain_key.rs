@@ -0,0 +1,128 @@
+use rand::rngs::OsRng;
+use rand::Rng;
+use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
+
+/// Number of words in a brain key suggested by cli_wallet
+pub const BRAIN_KEY_WORD_COUNT: usize = 16;
+
+/// Fewest distinct words `suggest_brain_key` accepts, so a brain key has at least 128 bits of
+/// randomness
+pub const MIN_WORD_LIST_SIZE: usize = 256;
+
+/// Normalizes a brain key the same way cli_wallet does: all whitespace is collapsed into single
+/// spaces, leading and trailing whitespace is dropped and ASCII letters are uppercased.
+/// https://gitlab.syncad.com/hive/hive/-/blob/master/libraries/wallet/wallet.cpp
+///
+/// # Arguments
+/// * `brain_key` - Brain key as entered by the user
+pub fn normalize_brain_key(brain_key: &str) -> String {
+    // Reserve up front, the result is never longer than the input. This way no partial copies of
+    // the brain key are left behind by reallocations.
+    let mut normalized = String::with_capacity(brain_key.len());
+
+    for word in brain_key
+        .split([' ', '\t', '\r', '\n', '\x0b', '\x0c'])
+        .filter(|word| !word.is_empty())
+    {
+        if !normalized.is_empty() {
+            normalized.push(' ');
+        }
+
+        normalized.push_str(word);
+    }
+
+    normalized.make_ascii_uppercase();
+    normalized
+}
+
+/// The word list passed to `suggest_brain_key` has fewer than `MIN_WORD_LIST_SIZE` distinct words
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WordListTooSmallError {
+    pub distinct_words: usize,
+}
+
+impl Display for WordListTooSmallError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "brain key word list has {} distinct words, at least {} are needed",
+            self.distinct_words, MIN_WORD_LIST_SIZE
+        )
+    }
+}
+
+impl std::error::Error for WordListTooSmallError {}
+
+/// Suggests a new, normalized, brain key of `BRAIN_KEY_WORD_COUNT` random words.
+///
+/// To generate brain keys that look like the ones from cli_wallet, pass the standard Hive/BitShares
+/// brain key dictionary as `word_list`. Key derivation does not depend on the word list, any brain
+/// key can be used with `PrivateKey::from_brain_key`. The dictionary is not bundled with this
+/// crate.
+///
+/// # Arguments
+/// * `word_list` - Dictionary to pick the words from, with at least `MIN_WORD_LIST_SIZE` distinct
+///   words
+pub fn suggest_brain_key(word_list: &[&str]) -> Result<String, WordListTooSmallError> {
+    // Words that only differ in case are the same once normalized
+    let distinct_words = word_list
+        .iter()
+        .map(|word| normalize_brain_key(word))
+        .filter(|word| !word.is_empty())
+        .collect::<HashSet<_>>()
+        .len();
+    if distinct_words < MIN_WORD_LIST_SIZE {
+        return Err(WordListTooSmallError { distinct_words });
+    }
+
+    let brain_key = (0..BRAIN_KEY_WORD_COUNT)
+        .map(|_| word_list[OsRng.gen_range(0..word_list.len())])
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    Ok(normalize_brain_key(&brain_key))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::brain_key::{
+        normalize_brain_key, suggest_brain_key, WordListTooSmallError, BRAIN_KEY_WORD_COUNT,
+        MIN_WORD_LIST_SIZE,
+    };
+
+    #[test]
+    fn normalize() {
+        assert_eq!(
+            normalize_brain_key("  hello \t World\r\n\x0bfoo\x0c "),
+            "HELLO WORLD FOO"
+        );
+        assert_eq!(normalize_brain_key(" \n\t "), "");
+    }
+
+    #[test]
+    fn suggest() {
+        let words: Vec<_> = (0..MIN_WORD_LIST_SIZE).map(|i| format!("w{}", i)).collect();
+        let word_list: Vec<_> = words.iter().map(String::as_str).collect();
+        let brain_key = suggest_brain_key(&word_list).unwrap();
+
+        assert_eq!(brain_key.split(' ').count(), BRAIN_KEY_WORD_COUNT);
+        assert!(brain_key
+            .split(' ')
+            .all(|word| words.contains(&word.to_lowercase())));
+
+        assert_eq!(
+            suggest_brain_key(&[]),
+            Err(WordListTooSmallError { distinct_words: 0 })
+        );
+        // Repeating a word adds no randomness
+        let mut repeated = word_list[1..].to_vec();
+        repeated.push("W1");
+        assert_eq!(
+            suggest_brain_key(&repeated),
+            Err(WordListTooSmallError {
+                distinct_words: MIN_WORD_LIST_SIZE - 1
+            })
+        );
+    }
+}
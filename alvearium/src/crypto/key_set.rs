@@ -0,0 +1,121 @@
+use crate::crypto::private_key::PrivateKey;
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{IntoWif, KeyRole};
+
+/// Private keys for all roles of an account
+pub struct KeySet {
+    pub owner: PrivateKey,
+    pub active: PrivateKey,
+    pub posting: PrivateKey,
+    pub memo: PrivateKey,
+}
+
+/// Public keys for all roles of an account
+#[derive(Debug)]
+pub struct PublicKeySet {
+    pub owner: PublicKey,
+    pub active: PublicKey,
+    pub posting: PublicKey,
+    pub memo: PublicKey,
+}
+
+impl KeySet {
+    /// Derives the keys for all roles from a username and password, in the same way as
+    /// `PrivateKey::from_login` and the Hive wallets do.
+    ///
+    /// # Arguments
+    /// * `username` - Name of the account
+    /// * `password` - Master password of the account, see `generate_password`
+    pub fn from_password(username: &str, password: &str) -> Result<Self, secp256k1::Error> {
+        Ok(Self {
+            owner: PrivateKey::from_login(username, password, KeyRole::Owner)?,
+            active: PrivateKey::from_login(username, password, KeyRole::Active)?,
+            posting: PrivateKey::from_login(username, password, KeyRole::Posting)?,
+            memo: PrivateKey::from_login(username, password, KeyRole::Memo)?,
+        })
+    }
+
+    pub fn get(&self, role: KeyRole) -> &PrivateKey {
+        match role {
+            KeyRole::Owner => &self.owner,
+            KeyRole::Active => &self.active,
+            KeyRole::Posting => &self.posting,
+            KeyRole::Memo => &self.memo,
+        }
+    }
+
+    pub fn create_public(&self, prefix: Option<[u8; 3]>) -> PublicKeySet {
+        PublicKeySet {
+            owner: self.owner.create_public(prefix),
+            active: self.active.create_public(prefix),
+            posting: self.posting.create_public(prefix),
+            memo: self.memo.create_public(prefix),
+        }
+    }
+}
+
+impl PublicKeySet {
+    pub fn get(&self, role: KeyRole) -> &PublicKey {
+        match role {
+            KeyRole::Owner => &self.owner,
+            KeyRole::Active => &self.active,
+            KeyRole::Posting => &self.posting,
+            KeyRole::Memo => &self.memo,
+        }
+    }
+}
+
+/// Generates a master password in the format used by the Hive wallets, a `P` followed by the WIF of
+/// a random private key.
+pub fn generate_password() -> String {
+    format!("P{}", PrivateKey::generate().to_wif())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::key_set::{generate_password, KeySet};
+    use crate::crypto::private_key::PrivateKey;
+    use crate::crypto::{FromWif, IntoWif, KeyRole};
+
+    #[test]
+    fn key_set_from_password() {
+        let keys = KeySet::from_password("alvearium", "P5secret").unwrap();
+        let public = keys.create_public(None);
+
+        let expected = [
+            (
+                KeyRole::Owner,
+                "5KdHczLLazmz12oV86XYvDCGEUHQZ7R5HAjDhz9sHvP2CCX8BB5",
+                "STM5D7w9HsvFQTESf7JRUgjxEVvaKfBDJu8dVrnYPhuNU6uEZqjsm",
+            ),
+            (
+                KeyRole::Active,
+                "5JR9nxWBiNeiuTKR93qPdSTzAzExF7NbCfrDYKfvYJ4b4fQjoBM",
+                "STM6iLxXEgK2n2jtfZSfEEGigzY645cHs84pM9z3xwQyMMukvgwij",
+            ),
+            (
+                KeyRole::Posting,
+                "5Jd1kzFFShDEwfAbZnHcoJid88NgZbkFSzJt9vVCu9tm2QcSEaD",
+                "STM6wu7XyaGeqQnp9YbmEGoFvH8bnyePyrTUsj8tmQtPTzu9KsqhG",
+            ),
+            (
+                KeyRole::Memo,
+                "5JiieaUwkmcsdaaUa7dUpK8UXnciVReJhVnhZ44oCzYncsdtuqi",
+                "STM7dmUbSwENQvPFwvwEgAgtVWPQKxcY3UCT5Eb64pc2XoMhzn6kY",
+            ),
+        ];
+
+        for (role, private_wif, public_wif) in expected {
+            assert_eq!(keys.get(role).to_wif(), private_wif);
+            assert_eq!(public.get(role).to_wif(), public_wif);
+        }
+    }
+
+    #[test]
+    fn generated_password_format() {
+        let password = generate_password();
+
+        assert!(password.starts_with("P5"));
+        assert!(PrivateKey::from_wif(&password[1..]).is_ok());
+    }
+}
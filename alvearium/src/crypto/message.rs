@@ -0,0 +1,94 @@
+use crate::crypto::private_key::PrivateKey;
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{sha256, signature_from_bytes, signature_to_bytes};
+
+/// Signs a message the same way Hive Keychain (`requestSignBuffer`) and hive-tx do.
+///
+/// The message is hashed using SHA-256 and the digest is signed canonically. The result is the
+/// hex encoded 65 byte signature, so it can be handed to any of those libraries for verification.
+///
+/// # Arguments
+/// * `key` - Key to sign with, for logins this is usually the posting key
+/// * `message` - Message to sign
+pub fn sign_message(key: &PrivateKey, message: impl AsRef<[u8]>) -> String {
+    let signature = key.sign_digest_canonical(sha256(message));
+
+    hex::encode(signature_to_bytes(&signature))
+}
+
+/// Verifies a hex encoded signature as produced by `sign_message`, Hive Keychain or hive-tx.
+///
+/// Returns `false` if the signature is malformed or was not made by `key`.
+///
+/// # Arguments
+/// * `key` - Public key that is expected to have signed the message
+/// * `message` - Message that was signed
+/// * `signature` - Hex encoded 65 byte signature
+pub fn verify_message(key: &PublicKey, message: impl AsRef<[u8]>, signature: &str) -> bool {
+    let signature = match hex::decode(signature)
+        .ok()
+        .and_then(|bytes| signature_from_bytes(&bytes))
+    {
+        Some(signature) => signature,
+        None => return false,
+    };
+
+    key.verify(message, &signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::private_key::PrivateKey;
+    use crate::crypto::{sign_message, verify_message};
+
+    #[test]
+    fn sign_and_verify_message() {
+        let key = PrivateKey::from_seed("alice").unwrap();
+        let signature = sign_message(&key, "alvearium login challenge");
+
+        assert_eq!(signature.len(), 130);
+        assert!(verify_message(
+            &key.create_public(None),
+            "alvearium login challenge",
+            &signature
+        ));
+    }
+
+    #[test]
+    fn verify_external_signature() {
+        let key = PrivateKey::from_seed("alice").unwrap().create_public(None);
+        let signature = "1f0420b29056e8aa6d6847006f8b45a0c4d6a34404dd35ebcfce4a41fc7fe683031f76a3d4c8b09aad2dd987a0af529b05c577f1fe4e286088c7a553c71d56b915";
+
+        assert!(verify_message(&key, "alvearium login challenge", signature));
+        assert!(!verify_message(&key, "another challenge", signature));
+    }
+
+    #[test]
+    fn reject_wrong_key_and_malformed_signature() {
+        let key = PrivateKey::from_seed("alice").unwrap();
+        let other = PrivateKey::from_seed("bob").unwrap().create_public(None);
+        let signature = sign_message(&key, "challenge");
+
+        assert!(!verify_message(&other, "challenge", &signature));
+        assert!(!verify_message(&other, "challenge", "not hex"));
+        assert!(!verify_message(&other, "challenge", &signature[..128]));
+
+        // Only 27..=34 are recovery bytes of fc
+        for recovery in ["00", "1a", "23", "ff"] {
+            let signature = format!("{}{}", recovery, &signature[2..]);
+            assert!(!verify_message(
+                &key.create_public(None),
+                "challenge",
+                &signature
+            ));
+        }
+        // The uncompressed form of the same recovery id
+        let recovery = u8::from_str_radix(&signature[..2], 16).unwrap() - 4;
+        let signature = format!("{:02x}{}", recovery, &signature[2..]);
+        assert!(verify_message(
+            &key.create_public(None),
+            "challenge",
+            &signature
+        ));
+    }
+}
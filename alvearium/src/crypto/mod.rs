@@ -1,30 +1,64 @@
+mod brain_key;
+pub mod key_set;
+mod message;
 pub mod private_key;
 pub mod public_key;
 
+pub use brain_key::{
+    normalize_brain_key, suggest_brain_key, WordListTooSmallError, BRAIN_KEY_WORD_COUNT,
+    MIN_WORD_LIST_SIZE,
+};
+pub use message::{sign_message, verify_message};
+
 use ripemd::{Digest, Ripemd160};
-use sha2::Sha256;
+use secp256k1::ecdsa::{RecoverableSignature, RecoveryId};
+use serde::{Deserialize, Serialize};
+use sha2::{Sha224, Sha256, Sha512};
+use std::fmt::{Display, Formatter};
 
 /// Hashes the input using ripemd-160
 ///
 /// # Arguments
 /// * `input` - Data to hash
-fn ripemd160(input: impl AsRef<[u8]>) -> [u8; 20] {
+pub(crate) fn ripemd160(input: impl AsRef<[u8]>) -> [u8; 20] {
     let mut hasher = Ripemd160::new();
     hasher.update(input);
     hasher.finalize().into()
 }
 
+/// Hashes the input using SHA-224
+///
+/// # Arguments
+/// * `input` - Data to hash
+pub(crate) fn sha224(input: impl AsRef<[u8]>) -> [u8; 28] {
+    let mut hasher = Sha224::new();
+
+    hasher.update(input);
+    hasher.finalize().into()
+}
+
 /// Hashes the input using SHA-256
 ///
 /// # Arguments
 /// * `input` - Data to hash
-fn sha256(input: impl AsRef<[u8]>) -> [u8; 32] {
+pub(crate) fn sha256(input: impl AsRef<[u8]>) -> [u8; 32] {
     let mut hasher = Sha256::new();
 
     hasher.update(input);
     hasher.finalize().into()
 }
 
+/// Hashes the input using SHA-512
+///
+/// # Arguments
+/// * `input` - Data to hash
+pub(crate) fn sha512(input: impl AsRef<[u8]>) -> [u8; 64] {
+    let mut hasher = Sha512::new();
+
+    hasher.update(input);
+    hasher.finalize().into()
+}
+
 /// Hashes the input using SHA-256 twice
 ///
 /// # Arguments
@@ -33,6 +67,50 @@ fn double_sha256(input: impl AsRef<[u8]>) -> [u8; 32] {
     sha256(sha256(input))
 }
 
+/// Serializes a signature the way fc does, the recovery id (+31) followed by the compact signature
+///
+/// # Arguments
+/// * `signature` - Signature to serialize
+pub(crate) fn signature_to_bytes(signature: &RecoverableSignature) -> [u8; 65] {
+    let (recovery_id, compact) = signature.serialize_compact();
+    let mut buffer: [u8; 65] = [0; 65];
+
+    // I guess recovery_id can never be larger than a u8 the code says it needs to be 0..=3
+    buffer[0] = recovery_id.to_i32() as u8 + 31;
+    buffer[1..].clone_from_slice(&compact);
+
+    buffer
+}
+
+/// Parses a 65 byte fc signature, returns `None` if it is malformed
+///
+/// # Arguments
+/// * `bytes` - Recovery id followed by the compact signature
+pub(crate) fn signature_from_bytes(bytes: &[u8]) -> Option<RecoverableSignature> {
+    if bytes.len() != 65 {
+        return None;
+    }
+
+    // fc accepts both the compressed (31..=34) and uncompressed (27..=30) recovery bytes
+    if !(27..=34).contains(&bytes[0]) {
+        return None;
+    }
+    let recovery_id = RecoveryId::from_i32(((bytes[0] - 27) & 3) as i32).ok()?;
+
+    RecoverableSignature::from_compact(&bytes[1..], recovery_id).ok()
+}
+
+/// Whether the S value of the signature is in the lower half of the curve order.
+///
+/// Every signature has a high-S twin that recovers the same key, hived only accepts the low-S one.
+pub(crate) fn is_low_s(signature: &RecoverableSignature) -> bool {
+    let standard = signature.to_standard();
+    let mut normalized = standard;
+    normalized.normalize_s();
+
+    standard == normalized
+}
+
 pub trait IntoWif {
     fn to_wif(&self) -> String;
 }
@@ -45,6 +123,8 @@ pub trait FromWif {
         Self: Sized;
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[serde(rename_all = "lowercase")]
 pub enum KeyRole {
     Owner,
     Active,
@@ -52,5 +132,29 @@ pub enum KeyRole {
     Memo,
 }
 
+impl KeyRole {
+    pub const ALL: [KeyRole; 4] = [
+        KeyRole::Owner,
+        KeyRole::Active,
+        KeyRole::Posting,
+        KeyRole::Memo,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KeyRole::Owner => "owner",
+            KeyRole::Active => "active",
+            KeyRole::Posting => "posting",
+            KeyRole::Memo => "memo",
+        }
+    }
+}
+
+impl Display for KeyRole {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
 pub(crate) const NETWORK_ID: u8 = 0x80;
 pub(crate) const DEFAULT_ADDRESS_PREFIX: [u8; 3] = [b'S', b'T', b'M'];
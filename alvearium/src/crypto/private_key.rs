@@ -1,15 +1,59 @@
+use rand::rngs::OsRng;
+use rand::RngCore;
 use secp256k1::ecdsa::RecoverableSignature;
 use secp256k1::Message;
-
-use crate::crypto::public_key::{PrivateKeyBuildError, PublicKey};
-use crate::crypto::{double_sha256, sha256, FromWif, IntoWif, KeyRole, NETWORK_ID};
-
+use std::fmt::{Debug, Display, Formatter};
+use std::str::FromStr;
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::Zeroizing;
+
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{
+    double_sha256, normalize_brain_key, sha256, sha512, FromWif, IntoWif, KeyRole, NETWORK_ID,
+};
+
+/// A secp256k1 private key.
+///
+/// The secret is zeroized when the key is dropped and never shown by `Debug`. Comparing keys
+/// happens in constant time. `secp256k1::SecretKey` is `Copy`, so the key passed to `from_key` and
+/// the copies made while signing are not wiped.
+///
+/// There is deliberately no `Display`, `Hash` or `Ord`, use `to_wif` to get the secret out.
+#[derive(Clone)]
 pub struct PrivateKey {
-    key: secp256k1::SecretKey,
+    /// Always a valid secp256k1 secret key
+    key: Zeroizing<[u8; 32]>,
     pub network_id: u8,
     compressed: bool,
 }
 
+impl Debug for PrivateKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PrivateKey")
+            .field("key", &"<redacted>")
+            .field("network_id", &self.network_id)
+            .field("compressed", &self.compressed)
+            .finish()
+    }
+}
+
+impl ConstantTimeEq for PrivateKey {
+    fn ct_eq(&self, other: &Self) -> Choice {
+        self.key.as_ref().ct_eq(other.key.as_ref())
+            & self.network_id.ct_eq(&other.network_id)
+            & (self.compressed as u8).ct_eq(&(other.compressed as u8))
+    }
+}
+
+/// Two keys are equal if they have the same WIF representation
+impl PartialEq for PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.ct_eq(other).into()
+    }
+}
+
+impl Eq for PrivateKey {}
+
 /// Implementation check, however the position used is different between the 2 implementations.
 /// Unsure where this difference comes from. Using the dhive one in this case.
 /// https://gitlab.syncad.com/hive/hive/-/blob/master/libraries/fc/src/crypto/elliptic_common.cpp#L176
@@ -23,6 +67,56 @@ fn is_canonical(signature: &RecoverableSignature) -> bool {
         && !(sa[32] == 0 && (sa[33] & 0x80 == 0))
 }
 
+#[derive(Debug)]
+pub enum PrivateKeyBuildError {
+    Decode(bs58::decode::Error),
+    Secp256k1(secp256k1::Error),
+    InvalidLength(usize),
+    InvalidNetworkId(u8),
+    Checksum([u8; 4], [u8; 4]),
+    InvalidCompressionByte(u8),
+}
+
+impl Display for PrivateKeyBuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PrivateKeyBuildError::Decode(_) => f.write_str("private key is not valid base58"),
+            PrivateKeyBuildError::Secp256k1(_) => f.write_str("not a valid secp256k1 secret key"),
+            PrivateKeyBuildError::InvalidLength(x) => write!(
+                f,
+                "private key is {} bytes long, expected 37 or 38 bytes",
+                x
+            ),
+            PrivateKeyBuildError::InvalidNetworkId(x) => write!(
+                f,
+                "private key has network id {:#04x}, expected {:#04x}",
+                x, NETWORK_ID
+            ),
+            PrivateKeyBuildError::Checksum(checksum, expected) => write!(
+                f,
+                "private key checksum {} does not match {}",
+                hex::encode(checksum),
+                hex::encode(expected)
+            ),
+            PrivateKeyBuildError::InvalidCompressionByte(x) => write!(
+                f,
+                "private key has compression byte {:#04x}, expected 0x01",
+                x
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PrivateKeyBuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PrivateKeyBuildError::Decode(e) => Some(e),
+            PrivateKeyBuildError::Secp256k1(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 impl FromWif for PrivateKey {
     type Err = PrivateKeyBuildError;
 
@@ -30,9 +124,11 @@ impl FromWif for PrivateKey {
     where
         Self: Sized,
     {
-        let r = bs58::decode(wif)
-            .into_vec()
-            .map_err(PrivateKeyBuildError::Decode)?;
+        let r = Zeroizing::new(
+            bs58::decode(wif)
+                .into_vec()
+                .map_err(PrivateKeyBuildError::Decode)?,
+        );
 
         // https://en.bitcoin.it/wiki/Wallet_import_format
         /*
@@ -49,7 +145,7 @@ impl FromWif for PrivateKey {
          */
 
         let network_id: u8;
-        let mut secret: [u8; 32] = Default::default();
+        let mut secret = Zeroizing::new([0u8; 32]);
         let mut compression: Option<u8> = None;
         let mut checksum: [u8; 4] = Default::default();
         let mut checksum_verify: [u8; 4] = Default::default();
@@ -86,18 +182,28 @@ impl FromWif for PrivateKey {
             }
         }
 
+        secp256k1::SecretKey::from_slice(secret.as_ref())
+            .map_err(PrivateKeyBuildError::Secp256k1)?;
+
         Ok(PrivateKey {
             compressed: compression.is_some(),
-            key: secp256k1::SecretKey::from_slice(&secret)
-                .map_err(PrivateKeyBuildError::Secp256k1)?,
+            key: secret,
             network_id,
         })
     }
 }
 
+impl FromStr for PrivateKey {
+    type Err = PrivateKeyBuildError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_wif(s)
+    }
+}
+
 impl IntoWif for PrivateKey {
     fn to_wif(&self) -> String {
-        let mut v = vec![];
+        let mut v = Zeroizing::new(vec![]);
 
         v.push(self.network_id);
         v.extend_from_slice(self.key.as_ref());
@@ -106,28 +212,42 @@ impl IntoWif for PrivateKey {
             v.push(0x01);
         }
 
-        let checksum = double_sha256(&v);
+        let checksum = double_sha256(v.as_slice());
 
         v.extend_from_slice(&checksum[0..4]);
 
-        bs58::encode(v).into_string()
+        bs58::encode(v.as_slice()).into_string()
     }
 }
 
 impl PrivateKey {
     pub fn from_key(key: secp256k1::SecretKey, network_id: Option<u8>) -> Self {
         Self {
-            key,
+            key: Zeroizing::new(key.secret_bytes()),
             network_id: network_id.unwrap_or(NETWORK_ID),
             compressed: false,
         }
     }
 
+    /// Generates a new random private key
+    pub fn generate() -> Self {
+        loop {
+            let mut secret = Zeroizing::new([0u8; 32]);
+            OsRng.fill_bytes(secret.as_mut());
+
+            // Only fails if the secret is zero or not below the curve order
+            if let Ok(key) = secp256k1::SecretKey::from_slice(secret.as_ref()) {
+                return Self::from_key(key, None);
+            }
+        }
+    }
+
     pub fn from_seed(seed: impl AsRef<[u8]>) -> Result<Self, secp256k1::Error> {
-        let key = sha256(seed);
+        let key = Zeroizing::new(sha256(seed));
+        secp256k1::SecretKey::from_slice(key.as_ref())?;
 
         Ok(Self {
-            key: secp256k1::SecretKey::from_slice(&key)?,
+            key,
             compressed: false,
             network_id: NETWORK_ID,
         })
@@ -139,29 +259,74 @@ impl PrivateKey {
         password: &str,
         key_role: KeyRole,
     ) -> Result<Self, secp256k1::Error> {
-        let role = match key_role {
-            KeyRole::Owner => "owner",
-            KeyRole::Active => "active",
-            KeyRole::Posting => "posting",
-            KeyRole::Memo => "memo",
-        };
+        let seed = Zeroizing::new(format!("{}{}{}", username, key_role, password));
+        Self::from_seed(seed.as_bytes())
+    }
+
+    /// Derives the key at `sequence` from a brain key, the same way cli_wallet does. The brain key
+    /// is normalized first, so differences in casing and whitespace result in the same key.
+    ///
+    /// # Arguments
+    /// * `brain_key` - Brain key, for example one created by `suggest_brain_key`
+    /// * `sequence` - Sequence number of the key, cli_wallet uses 0 for the first key
+    pub fn from_brain_key(brain_key: &str, sequence: u32) -> Result<Self, secp256k1::Error> {
+        let brain_key = Zeroizing::new(normalize_brain_key(brain_key));
+        let seed = Zeroizing::new(format!("{} {}", brain_key.as_str(), sequence));
+
+        Self::from_seed(Zeroizing::new(sha512(seed.as_bytes())).as_ref())
+    }
 
-        let seed = format!("{}{}{}", username, role, password);
-        Self::from_seed(&seed)
+    fn secret_key(&self) -> secp256k1::SecretKey {
+        secp256k1::SecretKey::from_slice(self.key.as_ref())
+            .expect("the secret is checked when the key is built")
     }
 
     pub fn create_public(&self, prefix: Option<[u8; 3]>) -> PublicKey {
         let secp = secp256k1::Secp256k1::signing_only();
         PublicKey::from_key(
-            secp256k1::PublicKey::from_secret_key(&secp, &self.key),
+            secp256k1::PublicKey::from_secret_key(&secp, &self.secret_key()),
             prefix,
         )
     }
 
+    /// Derives the ECDH shared secret between this key and `public_key`.
+    ///
+    /// Matches fc's `private_key::get_shared_secret`, which is the SHA-512 of the x-coordinate of
+    /// the shared point. Both sides of the exchange end up with the same secret.
+    /// https://gitlab.syncad.com/hive/hive/-/blob/master/libraries/fc/src/crypto/elliptic_secp256k1.cpp
+    ///
+    /// # Arguments
+    /// * `public_key` - Public key of the other party
+    pub fn shared_secret(&self, public_key: &PublicKey) -> [u8; 64] {
+        let point = Zeroizing::new(secp256k1::ecdh::shared_secret_point(
+            &public_key.key,
+            &self.secret_key(),
+        ));
+
+        sha512(&point[..32])
+    }
+
     pub fn sign_ecdsa_canonical(&self, message: impl AsRef<[u8]>) -> RecoverableSignature {
-        let secp = secp256k1::Secp256k1::new();
         let hashed_message = sha256(&message);
         let wrapped_message = Message::from_slice(&hashed_message).unwrap();
+
+        self.sign_canonical(&wrapped_message, message.as_ref())
+    }
+
+    /// Signs an already hashed message. The nonce is derived from the digest in the same way as
+    /// dhive and hive-tx, so signatures are identical to the ones produced by those libraries.
+    ///
+    /// # Arguments
+    /// * `digest` - SHA-256 digest of the message to sign
+    pub fn sign_digest_canonical(&self, digest: [u8; 32]) -> RecoverableSignature {
+        let wrapped_message = Message::from_slice(&digest).unwrap();
+
+        self.sign_canonical(&wrapped_message, &digest)
+    }
+
+    fn sign_canonical(&self, message: &Message, nonce_seed: &[u8]) -> RecoverableSignature {
+        let secp = secp256k1::Secp256k1::new();
+        let key = self.secret_key();
         let mut attempt: u8 = 0;
 
         /*
@@ -177,12 +342,8 @@ impl PrivateKey {
          */
         loop {
             attempt += 1;
-            let nonce_data = sha256([message.as_ref(), &[attempt]].concat());
-            let signature = secp.sign_ecdsa_recoverable_with_noncedata(
-                &wrapped_message,
-                &self.key,
-                &nonce_data,
-            );
+            let nonce_data = sha256([nonce_seed, &[attempt]].concat());
+            let signature = secp.sign_ecdsa_recoverable_with_noncedata(message, &key, &nonce_data);
 
             if is_canonical(&signature) {
                 return signature;
@@ -193,8 +354,10 @@ impl PrivateKey {
 
 #[cfg(test)]
 mod tests {
+    use std::error::Error;
     use std::str::FromStr;
 
+    use hex_literal::hex;
     use secp256k1::rand::rngs::OsRng;
     use secp256k1::Secp256k1;
 
@@ -209,7 +372,7 @@ mod tests {
         let key = PrivateKey::from_wif(wif).unwrap();
 
         assert_eq!(
-            key.key,
+            key.secret_key(),
             secp256k1::SecretKey::from_str(
                 "0C28FCA386C7A227600B2FE50B7CAE11EC86D3BF1FBE471BE89827E19D72AA1D"
             )
@@ -219,6 +382,24 @@ mod tests {
         assert_eq!(key.network_id, 0x80);
     }
 
+    #[test]
+    fn private_key_error_messages() {
+        // Valid base58, but too short to be a key
+        let length = PrivateKey::from_wif("5HueCGU8rMjxEXxiPuD5BDku4").unwrap_err();
+        assert_eq!(
+            length.to_string(),
+            "private key is 18 bytes long, expected 37 or 38 bytes"
+        );
+
+        let checksum = PrivateKey::from_wif("5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyTK")
+            .unwrap_err();
+        assert!(checksum.to_string().starts_with("private key checksum"));
+
+        let decode = PrivateKey::from_wif("5HueCGU8rMjxEXxiPuD5BDku4MkFqeZyd4dZ1jvhTVqvbTLvyT0")
+            .unwrap_err();
+        assert!(decode.source().is_some());
+    }
+
     #[test]
     fn private_key_full_cycle() {
         let wif = "5JMmGLTnJnm4mDm2bEjQqU1hPqPqUh3MSTuMDkv5vAKDricTYcZ".to_owned();
@@ -239,4 +420,62 @@ mod tests {
 
         assert!(is_canonical(&signature));
     }
+
+    #[test]
+    fn brain_key_derivation() {
+        let first = PrivateKey::from_brain_key("  hello World\tfoo ", 0).unwrap();
+        let second = PrivateKey::from_brain_key("HELLO WORLD FOO", 1).unwrap();
+
+        assert_eq!(
+            first.to_wif(),
+            "5HxYxuMjUFLgFmz3b8a2GgQrMY1rgRuLWdozLDJntjXi1UekGor"
+        );
+        assert_eq!(
+            second.to_wif(),
+            "5Hvz3pBrpVrc7t1Kh1pvwPNQzde2Li2RDtmJaTDe6wtLv8vFjC9"
+        );
+    }
+
+    #[test]
+    fn debug_is_redacted() {
+        let wif = "5JMmGLTnJnm4mDm2bEjQqU1hPqPqUh3MSTuMDkv5vAKDricTYcZ";
+        let key = PrivateKey::from_wif(wif).unwrap();
+        let debug = format!("{:?}", key);
+
+        assert!(debug.contains("<redacted>"));
+        assert!(!debug.contains(wif));
+        assert!(!debug.contains(&key.secret_key().display_secret().to_string()));
+    }
+
+    #[test]
+    fn private_key_equality() {
+        let wif = "5JMmGLTnJnm4mDm2bEjQqU1hPqPqUh3MSTuMDkv5vAKDricTYcZ";
+
+        assert_eq!(
+            PrivateKey::from_wif(wif).unwrap(),
+            PrivateKey::from_wif(wif).unwrap()
+        );
+        assert_ne!(
+            PrivateKey::from_wif(wif).unwrap(),
+            PrivateKey::from_seed("alice").unwrap()
+        );
+
+        let key: PrivateKey = wif.parse().unwrap();
+        assert_eq!(key.clone(), key);
+        assert_eq!(key.clone().to_wif(), wif);
+    }
+
+    #[test]
+    fn shared_secret() {
+        let alice = PrivateKey::from_seed("alice").unwrap();
+        let bob = PrivateKey::from_seed("bob").unwrap();
+
+        let expected = hex!(
+            "a71b4ec5a9577926a1d2aa1d9d99327fd3b68f6a1ea597200a0d890bd3331df3"
+            "00a2d49fec0b2b3e6969ce9263c5d6cf47c191c1ef149373ecc9f0d98116b598"
+        );
+
+        assert_eq!(alice.shared_secret(&bob.create_public(None)), expected);
+        assert_eq!(bob.shared_secret(&alice.create_public(None)), expected);
+    }
 }
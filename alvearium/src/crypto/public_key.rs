@@ -1,15 +1,30 @@
 use secp256k1::ecdsa::RecoverableSignature;
 use secp256k1::Message;
-use std::fmt::Debug;
+use std::cmp::Ordering;
+use std::fmt::{Debug, Display, Formatter};
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
 
 use crate::crypto::{ripemd160, sha256, FromWif, IntoWif, DEFAULT_ADDRESS_PREFIX};
+
+/// Moved to `private_key`, re-exported at its previous path
+pub use crate::crypto::private_key::PrivateKeyBuildError;
 use crate::enc::{encode_without_size, EncodeError};
 use crate::{HiveEncode, HiveEncoder};
 
-#[derive(Debug)]
+/// A secp256k1 public key with its address prefix.
+///
+/// Keys are compared, hashed and ordered by their compressed bytes like fc's `public_key_type`, so
+/// neither the prefix nor the compression take part in comparisons.
+///
+/// A key keeps the representation it was parsed from, so an uncompressed WIF is written back as
+/// an uncompressed WIF. The chain only accepts compressed keys, use `to_compressed` before putting
+/// a parsed key in an operation.
+#[derive(Debug, Clone)]
 pub struct PublicKey {
-    key: secp256k1::PublicKey,
+    pub(crate) key: secp256k1::PublicKey,
     prefix: [u8; 3],
+    compressed: bool,
 }
 
 impl PublicKey {
@@ -17,9 +32,41 @@ impl PublicKey {
         Self {
             key,
             prefix: prefix.unwrap_or(DEFAULT_ADDRESS_PREFIX),
+            compressed: true,
         }
     }
 
+    pub fn is_compressed(&self) -> bool {
+        self.compressed
+    }
+
+    /// Returns the same key in its compressed representation
+    pub fn to_compressed(&self) -> Self {
+        Self {
+            compressed: true,
+            ..self.clone()
+        }
+    }
+
+    /// Returns the 33 byte compressed or 65 byte uncompressed representation of the key
+    pub fn to_bytes(&self) -> Vec<u8> {
+        if self.compressed {
+            self.key.serialize().to_vec()
+        } else {
+            self.key.serialize_uncompressed().to_vec()
+        }
+    }
+
+    /// Recovers the key that signed the SHA-256 of `message`, `None` if the signature is invalid
+    pub fn recover(message: impl AsRef<[u8]>, signature: &RecoverableSignature) -> Option<Self> {
+        let secp = secp256k1::Secp256k1::verification_only();
+        let message = Message::from_slice(&sha256(message)).unwrap();
+
+        secp.recover_ecdsa(&message, signature)
+            .ok()
+            .map(|key| Self::from_key(key, None))
+    }
+
     pub fn verify(&self, message: impl AsRef<[u8]>, signature: &RecoverableSignature) -> bool {
         let secp = secp256k1::Secp256k1::verification_only();
         let message = sha256(message);
@@ -32,6 +79,82 @@ impl PublicKey {
     }
 }
 
+impl PartialEq for PublicKey {
+    fn eq(&self, other: &Self) -> bool {
+        self.key.serialize() == other.key.serialize()
+    }
+}
+
+impl Eq for PublicKey {}
+
+impl Hash for PublicKey {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.key.serialize().hash(state);
+    }
+}
+
+impl PartialOrd for PublicKey {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for PublicKey {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.serialize().cmp(&other.key.serialize())
+    }
+}
+
+impl Display for PublicKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.to_wif())
+    }
+}
+
+impl FromStr for PublicKey {
+    type Err = PublicKeyWifError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::from_wif(s)
+    }
+}
+
+#[derive(Debug)]
+pub enum PublicKeyWifError {
+    Decode(bs58::decode::Error),
+    Secp256k1(secp256k1::Error),
+    Invalid,
+    Checksum([u8; 4], [u8; 4]),
+}
+
+impl Display for PublicKeyWifError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublicKeyWifError::Decode(_) => f.write_str("public key is not valid base58"),
+            PublicKeyWifError::Secp256k1(_) => f.write_str("not a valid secp256k1 public key"),
+            PublicKeyWifError::Invalid => f.write_str(
+                "public key should be a 3 character prefix followed by a 33 or 65 byte key",
+            ),
+            PublicKeyWifError::Checksum(checksum, expected) => write!(
+                f,
+                "public key checksum {} does not match {}",
+                hex::encode(checksum),
+                hex::encode(expected)
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PublicKeyWifError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PublicKeyWifError::Decode(e) => Some(e),
+            PublicKeyWifError::Secp256k1(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
 impl FromWif for PublicKey {
     type Err = PublicKeyWifError;
 
@@ -82,37 +205,31 @@ impl FromWif for PublicKey {
         Ok(Self {
             key: secp256k1::PublicKey::from_slice(key).map_err(PublicKeyWifError::Secp256k1)?,
             prefix,
+            compressed: key.len() == 33,
         })
     }
 }
 
 impl IntoWif for PublicKey {
     fn to_wif(&self) -> String {
-        let mut v = vec![];
-        let checksum = ripemd160(self.key.serialize());
+        let mut v = self.to_bytes();
+        let checksum = ripemd160(&v);
 
         let prefix = String::from_utf8_lossy(&self.prefix);
 
-        v.extend_from_slice(&self.key.serialize());
         v.extend_from_slice(&checksum[0..4]);
 
         format!("{}{}", prefix, bs58::encode(v).into_string())
     }
 }
 
-#[derive(Debug)]
-pub enum PrivateKeyBuildError {
-    Decode(bs58::decode::Error),
-    Secp256k1(secp256k1::Error),
-    InvalidLength(usize),
-    InvalidNetworkId(u8),
-    Checksum([u8; 4], [u8; 4]),
-    InvalidCompressionByte(u8),
-}
-
 impl HiveEncode for PublicKey {
     fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
-        // Needs to be a 33 byte encode without length
+        // Needs to be a 33 byte encode without length, the chain does not accept uncompressed keys
+        if !self.compressed {
+            return Err(EncodeError::UncompressedPublicKey);
+        }
+
         encode_without_size(&self.key.serialize(), encoder)?;
 
         Ok(())
@@ -121,10 +238,14 @@ impl HiveEncode for PublicKey {
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashSet;
+    use std::error::Error;
     use std::str::FromStr;
 
+    use crate::crypto::private_key::PrivateKey;
     use crate::crypto::public_key::PublicKey;
     use crate::crypto::{FromWif, IntoWif};
+    use crate::enc::{encode_to_vec, EncodeError};
 
     #[test]
     fn wif_to_compressed_public_key() {
@@ -158,6 +279,84 @@ mod tests {
 
         assert_eq!(key.key, secp256k1::PublicKey::from_str("04d0de0aaeaefad02b8bdc8a01a1b8b11c696bd3d66a2c5f10780d95b7df42645cd85228a6fb29940e858e7e55842ae2bd115d1ed7cc0e82d934e929c97648cb0a").unwrap());
         assert_eq!(&key.prefix, b"ABC");
+        assert!(!key.is_compressed());
+    }
+
+    #[test]
+    fn uncompressed_public_key_full_cycle() {
+        let wif = "ABC3nJ4XSefGZrjiWxsUSQmAJFYhuxJ8kSCvkCaKngUYBbiqpmWywaxmdgTcWbPio55q7CHDTNBK9mhuK9fbHg6nAKXpZCb43";
+        let key = PublicKey::from_wif(wif).unwrap();
+
+        assert_eq!(key.to_wif(), wif);
+        assert_eq!(key.to_bytes().len(), 65);
+    }
+
+    #[test]
+    fn public_key_to_compressed() {
+        let wif = "ABC3nJ4XSefGZrjiWxsUSQmAJFYhuxJ8kSCvkCaKngUYBbiqpmWywaxmdgTcWbPio55q7CHDTNBK9mhuK9fbHg6nAKXpZCb43";
+        let uncompressed = PublicKey::from_wif(wif).unwrap();
+        let compressed = uncompressed.to_compressed();
+
+        assert!(compressed.is_compressed());
+        assert_eq!(
+            compressed.to_wif(),
+            "ABC6UUbAGbTLLWfY2gAc8XmjGBz2c7WT4fYB5r1L1aHDwAY88ujex"
+        );
+        assert_eq!(compressed, uncompressed);
+
+        let reparsed = PublicKey::from_wif(compressed.to_wif()).unwrap();
+        assert!(reparsed.is_compressed());
+        assert_eq!(reparsed.to_wif(), compressed.to_wif());
+    }
+
+    #[test]
+    fn uncompressed_public_key_rejected_by_chain_encoding() {
+        let wif = "ABC3nJ4XSefGZrjiWxsUSQmAJFYhuxJ8kSCvkCaKngUYBbiqpmWywaxmdgTcWbPio55q7CHDTNBK9mhuK9fbHg6nAKXpZCb43";
+        let key = PublicKey::from_wif(wif).unwrap();
+
+        assert!(matches!(
+            encode_to_vec(&key),
+            Err(EncodeError::UncompressedPublicKey)
+        ));
+        assert_eq!(encode_to_vec(key.to_compressed()).unwrap().len(), 33);
+
+        // JSON keeps the representation, both ways
+        let json = serde_json::to_string(&key).unwrap();
+        assert_eq!(json, format!("\"{}\"", wif));
+        let parsed: PublicKey = serde_json::from_str(&json).unwrap();
+        assert!(!parsed.is_compressed());
+    }
+
+    #[test]
+    fn public_key_comparison() {
+        let alice = PrivateKey::from_seed("alice").unwrap().create_public(None);
+        let bob = PrivateKey::from_seed("bob").unwrap().create_public(None);
+        let alice_test = PrivateKey::from_seed("alice")
+            .unwrap()
+            .create_public(Some(*b"TST"));
+
+        // The prefix does not matter, only the key itself
+        assert_eq!(alice, alice_test);
+        assert_ne!(alice, bob);
+
+        // Ordered by compressed bytes, bob starts with 0x02 and alice with 0x03
+        let mut keys = vec![alice.clone(), bob.clone()];
+        keys.sort();
+        assert_eq!(keys, vec![bob.clone(), alice.clone()]);
+
+        let set: HashSet<PublicKey> = [alice, bob, alice_test].into_iter().collect();
+        assert_eq!(set.len(), 2);
+    }
+
+    #[test]
+    fn public_key_from_str_and_display() {
+        let wif = "STM6rGZuZf3MBykvASN4xEgmJU5oNcwtZjyQc3x6ZL8Mts5UrpQfq";
+        let key: PublicKey = wif.parse().unwrap();
+
+        assert_eq!(key.to_string(), wif);
+        assert!("STM6rGZuZf3MBykvASN4xEgmJU5oNcwtZjyQc3x6ZL8Mts5UrpQfr"
+            .parse::<PublicKey>()
+            .is_err());
     }
 
     #[test]
@@ -168,12 +367,15 @@ mod tests {
 
         assert_eq!(key.is_err(), true);
     }
-}
 
-#[derive(Debug)]
-pub enum PublicKeyWifError {
-    Decode(bs58::decode::Error),
-    Secp256k1(secp256k1::Error),
-    Invalid,
-    Checksum([u8; 4], [u8; 4]),
+    #[test]
+    fn public_key_error_messages() {
+        let checksum = PublicKey::from_wif("STM6rGZuZf3MBykvASN4xEgmJU5oNcwtZjyQc3x6ZL8Mts5UrpQfr")
+            .unwrap_err();
+        assert!(checksum.to_string().starts_with("public key checksum"));
+
+        let decode = PublicKey::from_wif("STM0OIl").unwrap_err();
+        assert_eq!(decode.to_string(), "public key is not valid base58");
+        assert!(decode.source().is_some());
+    }
 }
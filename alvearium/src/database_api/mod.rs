@@ -3,12 +3,12 @@ mod version;
 pub use version::Version;
 
 use crate::block_api::get_block;
+use crate::client::{request, HiveClient};
 use crate::condenser_api::transaction::BlockchainMode;
 use crate::params::EmptyObjectParams;
 use crate::types::DynamicGlobalProperties;
+use crate::Error;
 use chrono::{DateTime, Utc};
-use jsonrpsee::core::client::ClientT;
-use jsonrpsee::http_client::HttpClient;
 
 pub struct TxSignProperties {
     pub time: DateTime<Utc>,
@@ -16,23 +16,23 @@ pub struct TxSignProperties {
     pub ref_block_prefix: String,
 }
 
-pub async fn get_dynamic_global_properties(
-    client: &HttpClient,
-) -> anyhow::Result<DynamicGlobalProperties> {
-    let response: DynamicGlobalProperties = client
-        .request(
-            "database_api.get_dynamic_global_properties",
-            EmptyObjectParams,
-        )
-        .await?;
+pub async fn get_dynamic_global_properties<C: HiveClient + ?Sized>(
+    client: &C,
+) -> Result<DynamicGlobalProperties, Error> {
+    let response: DynamicGlobalProperties = request(
+        client,
+        "database_api.get_dynamic_global_properties",
+        EmptyObjectParams,
+    )
+    .await?;
 
     Ok(response)
 }
 
-pub async fn get_tx_sign_properties(
-    client: &HttpClient,
+pub async fn get_tx_sign_properties<C: HiveClient + ?Sized>(
+    client: &C,
     mode: BlockchainMode,
-) -> anyhow::Result<TxSignProperties> {
+) -> Result<TxSignProperties, Error> {
     let properties = get_dynamic_global_properties(client).await?;
 
     match mode {
@@ -53,10 +53,79 @@ pub async fn get_tx_sign_properties(
     }
 }
 
-pub async fn get_version(client: &HttpClient) -> anyhow::Result<Version> {
-    let response: Version = client
-        .request("database_api.get_version", EmptyObjectParams)
-        .await?;
+pub async fn get_version<C: HiveClient + ?Sized>(client: &C) -> Result<Version, Error> {
+    let response: Version = request(client, "database_api.get_version", EmptyObjectParams).await?;
 
     Ok(response)
 }
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::client::MockClient;
+    use crate::database_api::get_dynamic_global_properties;
+    use chrono::{DateTime, Utc};
+    use serde_json::{json, Value};
+
+    /// `database_api.get_dynamic_global_properties` result of a node at the given blocks
+    pub(crate) fn dynamic_global_properties_json(
+        head_block_number: u32,
+        last_irreversible_block_num: u32,
+        time: DateTime<Utc>,
+    ) -> Value {
+        let supply = |nai: &str| json!({ "amount": "1000", "precision": 3, "nai": nai });
+        let time = time.format("%Y-%m-%dT%H:%M:%S").to_string();
+
+        json!({
+            "id": 0,
+            "head_block_number": head_block_number,
+            "head_block_id": format!("{:08x}{:032x}", head_block_number, 0),
+            "time": time,
+            "current_witness": "initminer",
+            "total_pow": 0,
+            "num_pow_witnesses": 0,
+            "virtual_supply": supply("@@000000021"),
+            "current_supply": supply("@@000000021"),
+            "init_hbd_supply": supply("@@000000013"),
+            "current_hbd_supply": supply("@@000000013"),
+            "total_vesting_fund_hive": supply("@@000000021"),
+            "total_vesting_shares": supply("@@000000037"),
+            "total_reward_fund_hive": supply("@@000000021"),
+            "total_reward_shares2": "0",
+            "pending_rewarded_vesting_shares": supply("@@000000037"),
+            "pending_rewarded_vesting_hive": supply("@@000000021"),
+            "hbd_interest_rate": 2000,
+            "hbd_print_rate": 10000,
+            "maximum_block_size": 65536,
+            "required_actions_partition_percent": 0,
+            "current_aslot": head_block_number,
+            "recent_slots_filled": "340282366920938463463374607431768211455",
+            "participation_count": 128,
+            "last_irreversible_block_num": last_irreversible_block_num,
+            "target_votes_per_period": null,
+            "delegation_return_period": 432000,
+            "reverse_auction_seconds": 0,
+            "available_account_subsidies": 0,
+            "hbd_stop_percent": 1000,
+            "hbd_start_percent": 900,
+            "next_maintenance_time": time,
+            "last_budget_time": time,
+            "content_reward_percent": 6500,
+            "vesting_reward_percent": 1500,
+            "downvote_pool_percent": 2500
+        })
+    }
+
+    #[tokio::test]
+    async fn dynamic_global_properties() {
+        let client = MockClient::new();
+        client.respond(
+            "database_api.get_dynamic_global_properties",
+            dynamic_global_properties_json(100, 80, Utc::now()),
+        );
+
+        let properties = get_dynamic_global_properties(&client).await.unwrap();
+
+        assert_eq!(properties.head_block_number, 100);
+        assert_eq!(properties.last_irreversible_block_num, 80);
+    }
+}
@@ -48,7 +48,8 @@ where
         where
             E: Error,
         {
-            PublicKey::from_wif(v).map_err(|_| E::custom("Error building public key from WIF"))
+            PublicKey::from_wif(v)
+                .map_err(|e| E::custom(format!("Error building public key from WIF: {}", e)))
         }
     }
 
@@ -0,0 +1,243 @@
+//! Decoding of the binary format of the Hive Blockchain, the counterpart of `enc`.
+
+use chrono::{DateTime, TimeZone, Utc};
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum DecodeError {
+    /// The input ended in the middle of a value
+    UnexpectedEnd,
+    /// The input does not describe a valid value, e.g. an unknown variant
+    InvalidValue(String),
+}
+
+impl Display for DecodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DecodeError::UnexpectedEnd => f.write_str("unexpected end of input"),
+            DecodeError::InvalidValue(value) => write!(f, "invalid value: {}", value),
+        }
+    }
+}
+
+impl std::error::Error for DecodeError {}
+
+pub trait Reader {
+    /// Fills `bytes` from the input
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError>;
+}
+
+impl<T: Reader> Reader for &mut T {
+    #[inline]
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        (**self).read(bytes)
+    }
+}
+
+impl Reader for &[u8] {
+    fn read(&mut self, bytes: &mut [u8]) -> Result<(), DecodeError> {
+        if self.len() < bytes.len() {
+            return Err(DecodeError::UnexpectedEnd);
+        }
+
+        let (head, tail) = self.split_at(bytes.len());
+        bytes.copy_from_slice(head);
+        *self = tail;
+
+        Ok(())
+    }
+}
+
+/// Value that can be read from the binary format.
+///
+/// Every value reads at least one byte, so a bogus length of a `Vec` runs out of input instead of
+/// decoding empty values forever. `void_t` extensions are decoded by reading their variant index.
+pub trait HiveDecode: Sized {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError>;
+}
+
+pub trait HiveDecoder {
+    type R: Reader;
+
+    fn reader(&mut self) -> &mut Self::R;
+}
+
+impl<T> HiveDecoder for &mut T
+where
+    T: HiveDecoder,
+{
+    type R = T::R;
+
+    fn reader(&mut self) -> &mut Self::R {
+        T::reader(self)
+    }
+}
+
+/// Reads `N` bytes that are not prefixed by their length, e.g. a hash or a signature
+pub fn decode_without_size<D: HiveDecoder, const N: usize>(
+    decoder: &mut D,
+) -> Result<[u8; N], DecodeError> {
+    let mut bytes = [0; N];
+    decoder.reader().read(&mut bytes)?;
+
+    Ok(bytes)
+}
+
+impl HiveDecode for bool {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        match u8::decode(decoder)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            b => Err(DecodeError::InvalidValue(format!("bool {}", b))),
+        }
+    }
+}
+
+macro_rules! primitive_impl {
+    ( $t:ty ) => {
+        impl HiveDecode for $t {
+            fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+                Ok(<$t>::from_le_bytes(decode_without_size(decoder)?))
+            }
+        }
+    };
+}
+
+primitive_impl!(u8);
+primitive_impl!(u16);
+primitive_impl!(u32);
+primitive_impl!(u64);
+primitive_impl!(i16);
+primitive_impl!(i32);
+primitive_impl!(i64);
+
+/// Reads an unsigned LEB128, the encoding of lengths and variant indices
+pub fn decode_leb128<D: HiveDecoder>(decoder: &mut D) -> Result<u64, DecodeError> {
+    let mut value = 0u64;
+
+    for shift in (0..64).step_by(7) {
+        let byte = u8::decode(decoder)?;
+        value |= ((byte & 0x7f) as u64) << shift;
+
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+
+    Err(DecodeError::InvalidValue("LEB128 overflows u64".to_owned()))
+}
+
+impl<T> HiveDecode for Vec<T>
+where
+    T: HiveDecode,
+{
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let len = decode_leb128(decoder)?;
+        // The length is not trusted for preallocation, the input ends before a bogus length does
+        let mut items = vec![];
+
+        for _ in 0..len {
+            items.push(T::decode(decoder)?);
+        }
+
+        Ok(items)
+    }
+}
+
+impl HiveDecode for String {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        String::from_utf8(Vec::decode(decoder)?)
+            .map_err(|e| DecodeError::InvalidValue(e.to_string()))
+    }
+}
+
+impl HiveDecode for DateTime<Utc> {
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        let seconds = u32::decode(decoder)?;
+
+        Utc.timestamp_opt(seconds as i64, 0)
+            .single()
+            .ok_or_else(|| DecodeError::InvalidValue(format!("time {}", seconds)))
+    }
+}
+
+impl<T1, T2> HiveDecode for (T1, T2)
+where
+    T1: HiveDecode,
+    T2: HiveDecode,
+{
+    fn decode<D: HiveDecoder>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok((T1::decode(decoder)?, T2::decode(decoder)?))
+    }
+}
+
+struct HiveDecoderImpl<R: Reader> {
+    reader: R,
+}
+
+impl<R: Reader> HiveDecoder for HiveDecoderImpl<R> {
+    type R = R;
+
+    fn reader(&mut self) -> &mut Self::R {
+        &mut self.reader
+    }
+}
+
+/// Decodes a value from the start of `bytes`, returns it with the number of bytes it took
+pub fn decode_from_slice<T>(bytes: &[u8]) -> Result<(T, usize), DecodeError>
+where
+    T: HiveDecode,
+{
+    let mut decoder = HiveDecoderImpl { reader: bytes };
+    let t = T::decode(&mut decoder)?;
+
+    Ok((t, bytes.len() - decoder.reader.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dec::{decode_from_slice, DecodeError};
+    use crate::enc::encode_to_vec;
+    use chrono::{DateTime, TimeZone, Utc};
+
+    #[test]
+    fn decode_encoded_values() {
+        let value = (
+            vec!["alice".to_owned(), "bob".to_owned()],
+            (Utc.with_ymd_and_hms(2022, 10, 20, 10, 0, 0).unwrap(), -5i16),
+        );
+        let mut bytes = encode_to_vec(&value).unwrap();
+        bytes.push(0xff);
+
+        let (decoded, len) =
+            decode_from_slice::<(Vec<String>, (DateTime<Utc>, i16))>(&bytes).unwrap();
+
+        assert_eq!(decoded, value);
+        assert_eq!(len, bytes.len() - 1);
+    }
+
+    #[test]
+    fn long_lengths() {
+        let value = vec![7u8; 300];
+        let bytes = encode_to_vec(&value).unwrap();
+
+        assert_eq!(&bytes[..2], &[0xac, 0x02]);
+        assert_eq!(decode_from_slice::<Vec<u8>>(&bytes).unwrap().0, value);
+    }
+
+    #[test]
+    fn reject_truncated_and_invalid_input() {
+        assert!(matches!(
+            decode_from_slice::<u32>(&[1, 2, 3]),
+            Err(DecodeError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            decode_from_slice::<Vec<u8>>(&[0xff, 0xff, 0xff, 0xff, 0x0f]),
+            Err(DecodeError::UnexpectedEnd)
+        ));
+        assert!(matches!(
+            decode_from_slice::<bool>(&[2]),
+            Err(DecodeError::InvalidValue(_))
+        ));
+    }
+}
@@ -26,6 +26,7 @@ SOFTWARE.
 --- END ORIGINAL LICENSE ---
 */
 use chrono::{DateTime, Utc};
+use std::fmt::{Display, Formatter};
 
 pub trait Writer {
     fn write(&mut self, bytes: &[u8]) -> Result<(), EncodeError>;
@@ -38,11 +39,28 @@ impl<T: Writer> Writer for &mut T {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum EncodeError {
     Error,
+    UncompressedPublicKey,
+    /// A value cannot be represented in the binary format, e.g. a malformed hex string
+    InvalidValue(String),
 }
 
+impl Display for EncodeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EncodeError::Error => f.write_str("cannot write encoded value"),
+            EncodeError::UncompressedPublicKey => {
+                f.write_str("uncompressed public keys cannot be encoded")
+            }
+            EncodeError::InvalidValue(value) => write!(f, "invalid value: {}", value),
+        }
+    }
+}
+
+impl std::error::Error for EncodeError {}
+
 pub trait HiveEncode {
     fn encode<E: HiveEncoder>(&self, encoder: &mut E) -> Result<(), EncodeError>;
 }
@@ -0,0 +1,365 @@
+use crate::block_api::BlockVerifyError;
+use jsonrpsee::types::ErrorObject;
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::fmt::{Display, Formatter};
+
+/// Error returned by the API functions
+#[derive(Debug)]
+pub enum Error {
+    /// The node could not be reached, timed out or sent a response that could not be parsed
+    Transport(jsonrpsee::core::Error),
+    /// The node processed the request and rejected it
+    Rpc(RpcError),
+    /// The result of the node does not match the expected type
+    Json(serde_json::Error),
+    /// The requested block does not exist yet
+    BlockNotFound(u32),
+    /// The node returned a block id that does not start with a block number
+    InvalidBlockId(String),
+    /// The node returned a block that does not match its transactions
+    InvalidBlock(u32, BlockVerifyError),
+    /// The node answered a batch with a different number of results than it had requests
+    BatchSizeMismatch { requests: usize, results: usize },
+    /// The batch has no result for the item, it belongs to another batch
+    MissingBatchResult(usize),
+}
+
+impl Error {
+    /// Kind of the node side error, `None` if the node did not answer
+    pub fn rpc_kind(&self) -> Option<RpcErrorKind> {
+        match self {
+            Error::Rpc(e) => Some(e.kind()),
+            _ => None,
+        }
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Error::Transport(_) => f.write_str("request failed"),
+            Error::Rpc(e) => write!(f, "{}", e),
+            Error::Json(_) => f.write_str("unexpected result from node"),
+            Error::BlockNotFound(n) => write!(f, "block {} not found", n),
+            Error::InvalidBlockId(id) => write!(f, "invalid block id {}", id),
+            Error::InvalidBlock(n, _) => write!(f, "invalid block {}", n),
+            Error::BatchSizeMismatch { requests, results } => write!(
+                f,
+                "node answered a batch of {} requests with {} results",
+                requests, results
+            ),
+            Error::MissingBatchResult(index) => write!(f, "no result for batch item {}", index),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Transport(e) => Some(e),
+            // Displayed as the error itself
+            Error::Rpc(e) => e.source(),
+            Error::Json(e) => Some(e),
+            Error::InvalidBlock(_, e) => Some(e),
+            Error::BlockNotFound(_)
+            | Error::InvalidBlockId(_)
+            | Error::BatchSizeMismatch { .. }
+            | Error::MissingBatchResult(_) => None,
+        }
+    }
+}
+
+impl From<jsonrpsee::core::Error> for Error {
+    fn from(e: jsonrpsee::core::Error) -> Self {
+        match e {
+            jsonrpsee::core::Error::Call(error) => Error::Rpc(RpcError::from_error_object(&error)),
+            e => Error::Transport(e),
+        }
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<RpcError> for Error {
+    fn from(e: RpcError) -> Self {
+        Error::Rpc(e)
+    }
+}
+
+/// What went wrong on the node, so callers can decide whether to retry, rebuild or give up
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RpcErrorKind {
+    /// The transaction expired before it was applied, build a new one
+    Expired,
+    /// The transaction was already included, do not broadcast it again
+    Duplicate,
+    /// The transaction is not signed with the required keys
+    MissingAuthority,
+    /// The reference block does not match the node's chain
+    Tapos,
+    /// The account does not have enough resource credits, wait for them to regenerate
+    ResourceCredits,
+    /// The account exceeded its bandwidth, only before HF20
+    Bandwidth,
+    /// Any other error
+    Other,
+}
+
+/// JSON-RPC error returned by hived
+#[derive(Debug, Clone)]
+pub struct RpcError {
+    /// JSON-RPC error code, -32000 for most assertions
+    pub code: i32,
+    /// Message of the node, usually the exception message joined with the failed assertion
+    pub message: String,
+    /// The fc exception behind the error, `None` if the node did not send one
+    pub data: Option<RpcErrorData>,
+}
+
+/// fc exception as sent in the `data` of hived's errors
+#[derive(Deserialize, Debug, Clone)]
+pub struct RpcErrorData {
+    /// fc exception code, e.g. `4100000` for `assert_exception`
+    pub code: i64,
+    /// fc exception name, e.g. `tx_missing_posting_auth`
+    pub name: String,
+    pub message: String,
+    /// Assertions that failed, innermost first
+    #[serde(default)]
+    pub stack: Vec<RpcErrorStackEntry>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct RpcErrorStackEntry {
+    #[serde(default)]
+    pub context: Option<RpcErrorContext>,
+    /// Message with `${name}` placeholders for the values in `data`
+    pub format: String,
+    #[serde(default)]
+    pub data: Map<String, Value>,
+}
+
+/// Where in hived an assertion failed
+#[derive(Deserialize, Debug, Clone)]
+pub struct RpcErrorContext {
+    pub level: String,
+    pub file: String,
+    pub line: u32,
+    pub method: String,
+}
+
+impl RpcErrorStackEntry {
+    /// Returns `format` with the placeholders replaced by their values
+    pub fn formatted(&self) -> String {
+        let mut formatted = self.format.clone();
+
+        for (name, value) in &self.data {
+            let value = match value {
+                Value::String(s) => s.clone(),
+                v => v.to_string(),
+            };
+            formatted = formatted.replace(&format!("${{{}}}", name), &value);
+        }
+
+        formatted
+    }
+}
+
+impl RpcError {
+    pub(crate) fn from_error_object(error: &ErrorObject) -> RpcError {
+        RpcError {
+            code: error.code(),
+            message: error.message().to_owned(),
+            data: error
+                .data()
+                .and_then(|data| serde_json::from_str(data.get()).ok()),
+        }
+    }
+
+    pub fn kind(&self) -> RpcErrorKind {
+        let name = self.data.as_ref().map_or("", |data| data.name.as_str());
+        let formats = self
+            .data
+            .iter()
+            .flat_map(|data| data.stack.iter())
+            .map(|entry| entry.format.as_str());
+        let texts: Vec<&str> = formats.chain([self.message.as_str()]).collect();
+        let any = |pattern: &str| texts.iter().any(|text| text.contains(pattern));
+
+        if any("now < trx.expiration") || any("transaction expiration") {
+            RpcErrorKind::Expired
+        } else if any("Duplicate transaction check failed") {
+            RpcErrorKind::Duplicate
+        } else if name.starts_with("tx_missing_") || any("Missing Authority") {
+            RpcErrorKind::MissingAuthority
+        } else if name.contains("tapos") || any("ref_block_prefix") {
+            RpcErrorKind::Tapos
+        } else if any("rc_needed") || any("RC mana") {
+            RpcErrorKind::ResourceCredits
+        } else if any("bandwidth") {
+            RpcErrorKind::Bandwidth
+        } else {
+            RpcErrorKind::Other
+        }
+    }
+}
+
+impl Display for RpcError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "node returned error {}: {}", self.code, self.message)?;
+
+        if let Some(entry) = self.data.as_ref().and_then(|data| data.stack.first()) {
+            write!(f, " ({})", entry.formatted())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for RpcError {}
+
+#[cfg(test)]
+mod tests {
+    use crate::error::{Error, RpcErrorKind};
+    use jsonrpsee::types::ErrorObject;
+    use serde_json::{json, Value};
+
+    fn node_error(message: &str, data: Value) -> Error {
+        jsonrpsee::core::Error::Call(ErrorObject::owned(-32000, message, Some(data))).into()
+    }
+
+    fn assert_exception(format: &str, data: Value) -> Value {
+        json!({
+            "code": 4100000,
+            "name": "assert_exception",
+            "message": "Assert Exception",
+            "stack": [{
+                "context": {
+                    "level": "error",
+                    "file": "database.cpp",
+                    "line": 3834,
+                    "method": "_apply_transaction",
+                    "hostname": "",
+                    "timestamp": "2022-10-20T10:00:00"
+                },
+                "format": format,
+                "data": data
+            }]
+        })
+    }
+
+    #[test]
+    fn missing_authority() {
+        let error = node_error(
+            "missing required posting authority:Missing Posting Authority alice",
+            json!({
+                "code": 3010000,
+                "name": "tx_missing_posting_auth",
+                "message": "missing required posting authority",
+                "stack": [{
+                    "context": {
+                        "level": "error",
+                        "file": "transaction_util.hpp",
+                        "line": 63,
+                        "method": "verify_authority",
+                        "hostname": "",
+                        "timestamp": "2022-10-20T10:00:00"
+                    },
+                    "format": "Missing Posting Authority ${id}",
+                    "data": { "id": "alice", "posting": {} }
+                }]
+            }),
+        );
+
+        assert_eq!(error.rpc_kind(), Some(RpcErrorKind::MissingAuthority));
+
+        let Error::Rpc(rpc) = &error else {
+            panic!("expected a node error");
+        };
+        let data = rpc.data.as_ref().unwrap();
+        assert_eq!(data.code, 3010000);
+        assert_eq!(data.stack[0].context.as_ref().unwrap().line, 63);
+        assert_eq!(
+            error.to_string(),
+            "node returned error -32000: missing required posting authority:Missing Posting \
+             Authority alice (Missing Posting Authority alice)"
+        );
+    }
+
+    #[test]
+    fn display_without_source() {
+        let json = serde_json::from_str::<Value>("{").unwrap_err();
+        let message = json.to_string();
+        let error = Error::from(json);
+
+        // Error chain printers show the source after the message, it is not repeated
+        assert_eq!(error.to_string(), "unexpected result from node");
+        assert_eq!(
+            std::error::Error::source(&error).unwrap().to_string(),
+            message
+        );
+    }
+
+    #[test]
+    fn classify_assertions() {
+        let cases = [
+            (
+                "now < trx.expiration: ",
+                json!({ "now": "2022-10-20T10:00:00", "trx.exp": "2022-10-20T09:59:00" }),
+                RpcErrorKind::Expired,
+            ),
+            (
+                "Duplicate transaction check failed",
+                json!({ "trx_ix": "0000" }),
+                RpcErrorKind::Duplicate,
+            ),
+            (
+                "Account: ${account} has ${rc_current} RC, needs ${rc_needed} RC. Please wait to \
+                 transact, or power up HIVE.",
+                json!({ "account": "alice", "rc_current": 1, "rc_needed": 2 }),
+                RpcErrorKind::ResourceCredits,
+            ),
+            (
+                "Account: ${account} bandwidth limit exceeded. Please wait to transact or power \
+                 up HIVE.",
+                json!({ "account": "alice" }),
+                RpcErrorKind::Bandwidth,
+            ),
+            (
+                "trx.ref_block_prefix == tapos_block_summary: ",
+                json!({}),
+                RpcErrorKind::Tapos,
+            ),
+            ("false: ", json!({}), RpcErrorKind::Other),
+        ];
+
+        for (format, data, kind) in cases {
+            let error = node_error("Assert Exception", assert_exception(format, data));
+            assert_eq!(error.rpc_kind(), Some(kind), "{}", format);
+        }
+    }
+
+    #[test]
+    fn error_without_data() {
+        let error = node_error("Could not find method", Value::Null);
+
+        let Error::Rpc(rpc) = &error else {
+            panic!("expected a node error");
+        };
+        assert!(rpc.data.is_none());
+        assert_eq!(rpc.kind(), RpcErrorKind::Other);
+    }
+
+    #[test]
+    fn transport_error() {
+        let error = Error::from(jsonrpsee::core::Error::RequestTimeout);
+
+        assert!(matches!(error, Error::Transport(_)));
+        assert_eq!(error.rpc_kind(), None);
+    }
+}
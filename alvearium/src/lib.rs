@@ -1,23 +1,43 @@
 pub mod block_api;
+pub mod block_log;
+pub mod client;
 pub mod condenser_api;
 pub mod crypto;
 pub mod database_api;
+mod error;
+pub mod signer;
+pub mod transaction_status_api;
 pub mod types;
+pub mod wallet;
 
 mod de;
+pub mod dec;
 pub mod enc;
 pub mod operation;
 mod params;
 mod ser;
 
+pub use dec::HiveDecode;
+pub use dec::HiveDecoder;
 pub use enc::HiveEncode;
 pub use enc::HiveEncoder;
+pub use error::{Error, RpcError, RpcErrorContext, RpcErrorData, RpcErrorKind, RpcErrorStackEntry};
 use jsonrpsee::http_client::HttpClientBuilder;
+use jsonrpsee::ws_client::WsClientBuilder;
 
 pub use jsonrpsee::http_client::HttpClient;
+pub use jsonrpsee::ws_client::WsClient;
 
 pub fn create_default_client(
     target: impl AsRef<str>,
 ) -> Result<HttpClient, jsonrpsee::core::Error> {
     HttpClientBuilder::default().build(target)
 }
+
+/// Connects to a node over WebSocket, e.g. `ws://localhost:8090`
+///
+/// The connection is kept open, which saves the HTTP round trip of every request when following
+/// the chain with `block_api::subscribe_new_blocks`.
+pub async fn create_ws_client(target: impl AsRef<str>) -> Result<WsClient, jsonrpsee::core::Error> {
+    WsClientBuilder::default().build(target).await
+}
@@ -1,3 +1,4 @@
+use crate::crypto::key_set::PublicKeySet;
 use crate::crypto::public_key::PublicKey;
 use crate::operation::{Asset, AuthorityType};
 use alvearium_derive::HiveEncode;
@@ -17,3 +18,28 @@ pub struct AccountCreateWithDelegation {
     pub json_metadata: String,
     pub extensions: Vec<()>,
 }
+
+impl AccountCreateWithDelegation {
+    /// Creates the operation with single key authorities from `keys`
+    pub fn new(
+        fee: Asset,
+        delegation: Asset,
+        creator: String,
+        new_account_name: String,
+        keys: PublicKeySet,
+        json_metadata: String,
+    ) -> Self {
+        Self {
+            fee,
+            delegation,
+            creator,
+            new_account_name,
+            owner: keys.owner.into(),
+            active: keys.active.into(),
+            posting: keys.posting.into(),
+            memo_key: keys.memo,
+            json_metadata,
+            extensions: vec![],
+        }
+    }
+}
@@ -26,6 +26,17 @@ pub struct AuthorityType {
     pub key_auths: Vec<(PublicKey, u16)>,
 }
 
+impl From<PublicKey> for AuthorityType {
+    /// Authority that is satisfied by a signature of this single key
+    fn from(key: PublicKey) -> Self {
+        Self {
+            weight_threshold: 1,
+            account_auths: vec![],
+            key_auths: vec![(key, 1)],
+        }
+    }
+}
+
 #[derive(Deserialize, Serialize, Debug)]
 pub enum AssetSymbol {
     HIVE,
@@ -53,7 +64,16 @@ impl Display for AssetSymbol {
     }
 }
 
-pub struct NaiError;
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NaiError(pub String);
+
+impl Display for NaiError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown asset NAI {}", self.0)
+    }
+}
+
+impl std::error::Error for NaiError {}
 
 impl AssetSymbol {
     pub fn try_from_nai(nai: &str) -> Result<AssetSymbol, NaiError> {
@@ -61,7 +81,7 @@ impl AssetSymbol {
             "@@00000013" => AssetSymbol::HBD,
             "@@00000021" => AssetSymbol::HIVE,
             "@@00000037" => AssetSymbol::VESTS,
-            _ => return Err(NaiError),
+            _ => return Err(NaiError(nai.to_owned())),
         };
 
         Ok(symbol)
@@ -15,3 +15,12 @@ impl ToRpcParams for EmptyArrayParams {
         Ok(Some(RawValue::from_string("[]".to_owned())?))
     }
 }
+
+/// Params that were already serialized, passed through as they are
+pub struct RawParams(pub Option<Box<RawValue>>);
+
+impl ToRpcParams for RawParams {
+    fn to_rpc_params(self) -> Result<Option<Box<RawValue>>, jsonrpsee::core::Error> {
+        Ok(self.0)
+    }
+}
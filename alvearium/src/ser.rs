@@ -16,6 +16,8 @@ impl Serialize for PublicKey {
     where
         S: Serializer,
     {
+        // Written in the representation it was parsed from, like `Deserialize` accepts it. Only
+        // the binary encoding, which signing needs, rejects uncompressed keys.
         serializer.serialize_str(&self.to_wif())
     }
 }
@@ -0,0 +1,217 @@
+#[cfg(unix)]
+pub mod remote;
+
+use crate::crypto::private_key::PrivateKey;
+use crate::crypto::public_key::PublicKey;
+use crate::enc::EncodeError;
+use crate::wallet::{Wallet, WalletError};
+use async_trait::async_trait;
+use secp256k1::ecdsa::RecoverableSignature;
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum SignerError {
+    UnknownKey,
+    InvalidSignature,
+    Remote(String),
+    Wallet(WalletError),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The transaction cannot be encoded to compute the digest to sign
+    Encode(EncodeError),
+}
+
+impl Display for SignerError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SignerError::UnknownKey => f.write_str("signer has no key for this public key"),
+            SignerError::InvalidSignature => f.write_str("signer returned an invalid signature"),
+            SignerError::Remote(e) => write!(f, "remote signer failed: {}", e),
+            SignerError::Wallet(_) => f.write_str("wallet failed to sign"),
+            SignerError::Io(_) => f.write_str("failed to reach signer"),
+            SignerError::Json(_) => f.write_str("malformed signer message"),
+            SignerError::Encode(_) => f.write_str("cannot encode transaction"),
+        }
+    }
+}
+
+impl std::error::Error for SignerError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SignerError::Wallet(e) => Some(e),
+            SignerError::Io(e) => Some(e),
+            SignerError::Json(e) => Some(e),
+            SignerError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<WalletError> for SignerError {
+    fn from(e: WalletError) -> Self {
+        SignerError::Wallet(e)
+    }
+}
+
+impl From<std::io::Error> for SignerError {
+    fn from(e: std::io::Error) -> Self {
+        SignerError::Io(e)
+    }
+}
+
+impl From<EncodeError> for SignerError {
+    fn from(e: EncodeError) -> Self {
+        SignerError::Encode(e)
+    }
+}
+
+impl From<serde_json::Error> for SignerError {
+    fn from(e: serde_json::Error) -> Self {
+        SignerError::Json(e)
+    }
+}
+
+/// Something that can create signatures, without exposing where the private keys live.
+///
+/// Implemented for a single `PrivateKey`, a `Wallet` and a `RemoteSigner`. Implement it yourself to
+/// sign with an HSM or to use a fake signer in tests.
+#[async_trait]
+pub trait Signer: Send + Sync {
+    /// Public keys this signer can create signatures for
+    async fn public_keys(&self) -> Result<Vec<PublicKey>, SignerError>;
+
+    /// Signs a SHA-256 digest with the private key belonging to `public_key`
+    ///
+    /// # Arguments
+    /// * `digest` - Digest to sign, for transactions see `UnsignedTransaction::digest`
+    /// * `public_key` - Public key of the private key to sign with
+    async fn sign_digest(
+        &self,
+        digest: [u8; 32],
+        public_key: &PublicKey,
+    ) -> Result<RecoverableSignature, SignerError>;
+}
+
+#[async_trait]
+impl Signer for PrivateKey {
+    async fn public_keys(&self) -> Result<Vec<PublicKey>, SignerError> {
+        Ok(vec![self.create_public(None)])
+    }
+
+    async fn sign_digest(
+        &self,
+        digest: [u8; 32],
+        public_key: &PublicKey,
+    ) -> Result<RecoverableSignature, SignerError> {
+        if self.create_public(None) != *public_key {
+            return Err(SignerError::UnknownKey);
+        }
+
+        Ok(self.sign_digest_canonical(digest))
+    }
+}
+
+#[async_trait]
+impl Signer for Wallet {
+    async fn public_keys(&self) -> Result<Vec<PublicKey>, SignerError> {
+        Ok(self
+            .keys()?
+            .iter()
+            .map(|key| key.public_key.clone())
+            .collect())
+    }
+
+    async fn sign_digest(
+        &self,
+        digest: [u8; 32],
+        public_key: &PublicKey,
+    ) -> Result<RecoverableSignature, SignerError> {
+        let key = self.key_for(public_key)?.ok_or(SignerError::UnknownKey)?;
+
+        Ok(key.sign_digest_canonical(digest))
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::condenser_api::operation::Operation;
+    use crate::condenser_api::transaction::UnsignedTransaction;
+    use crate::crypto::private_key::PrivateKey;
+    use crate::crypto::KeyRole;
+    use crate::operation::CustomJson;
+    use crate::signer::{Signer, SignerError};
+    use crate::wallet::{KdfParams, Wallet};
+    use chrono::Utc;
+
+    pub(crate) fn unsigned_transaction() -> UnsignedTransaction {
+        UnsignedTransaction {
+            ref_block_num: 1,
+            ref_block_prefix: 2,
+            expiration: Utc::now(),
+            operations: vec![Operation::CustomJson(CustomJson {
+                required_auths: vec![],
+                required_posting_auths: vec!["alice".to_owned()],
+                id: "alvearium".to_owned(),
+                json: "{}".to_owned(),
+            })],
+            extensions: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn private_key_signer() {
+        let key = PrivateKey::from_seed("alice").unwrap();
+        let public_keys = key.public_keys().await.unwrap();
+        let transaction = unsigned_transaction();
+        let digest = transaction.digest(None).unwrap();
+
+        let signed = transaction
+            .sign_with_signer(&key, &public_keys, None)
+            .await
+            .unwrap();
+
+        assert_eq!(signed.signatures.len(), 1);
+        assert_eq!(
+            key.sign_digest(digest, &public_keys[0]).await.unwrap(),
+            key.sign_digest_canonical(digest)
+        );
+
+        let other = PrivateKey::from_seed("bob").unwrap().create_public(None);
+        assert!(matches!(
+            key.sign_digest(digest, &other).await,
+            Err(SignerError::UnknownKey)
+        ));
+    }
+
+    #[tokio::test]
+    async fn wallet_signer() {
+        let params = KdfParams {
+            log_n: 4,
+            r: 8,
+            p: 1,
+        };
+        let mut wallet = Wallet::with_kdf_params("hunter2", params).unwrap();
+        wallet
+            .import_key(
+                PrivateKey::from_seed("alice").unwrap(),
+                Some("alice"),
+                Some(KeyRole::Posting),
+            )
+            .unwrap();
+
+        let public_keys = wallet.public_keys().await.unwrap();
+        assert_eq!(public_keys.len(), 1);
+
+        let signed = unsigned_transaction()
+            .sign_with_signer(&wallet, &public_keys, None)
+            .await
+            .unwrap();
+        assert_eq!(signed.signatures.len(), 1);
+
+        wallet.lock();
+        assert!(matches!(
+            wallet.public_keys().await,
+            Err(SignerError::Wallet(_))
+        ));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{signature_from_bytes, signature_to_bytes, FromWif, IntoWif};
+use crate::signer::{Signer, SignerError};
+use async_trait::async_trait;
+use secp256k1::ecdsa::RecoverableSignature;
+use secp256k1::Message;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+
+/*
+The protocol between `RemoteSigner` and `serve` is newline delimited JSON over a unix socket, one
+request and one response per line:
+
+-> {"method":"public_keys"}
+<- {"public_keys":["STM..."]}
+-> {"method":"sign_digest","digest":"<hex>","public_key":"STM..."}
+<- {"signature":"<hex>"}
+
+Any request can be answered with {"error":"<message>"} instead.
+ */
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(tag = "method", rename_all = "snake_case")]
+enum Request {
+    PublicKeys,
+    SignDigest { digest: String, public_key: String },
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Response {
+    PublicKeys(Vec<PublicKey>),
+    Signature(String),
+    Error(String),
+}
+
+/// Signer that forwards requests to another process over a unix socket, see `serve`
+pub struct RemoteSigner {
+    path: PathBuf,
+}
+
+impl RemoteSigner {
+    /// # Arguments
+    /// * `path` - Path of the unix socket the signing process listens on
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    async fn request(&self, request: &Request) -> Result<Response, SignerError> {
+        let stream = UnixStream::connect(&self.path).await?;
+        let (reader, mut writer) = stream.into_split();
+
+        let mut line = serde_json::to_string(request)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+
+        let mut response = String::new();
+        BufReader::new(reader).read_line(&mut response).await?;
+
+        match serde_json::from_str(&response)? {
+            Response::Error(e) => Err(SignerError::Remote(e)),
+            response => Ok(response),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for RemoteSigner {
+    async fn public_keys(&self) -> Result<Vec<PublicKey>, SignerError> {
+        match self.request(&Request::PublicKeys).await? {
+            Response::PublicKeys(keys) => Ok(keys),
+            _ => Err(SignerError::Remote("Unexpected response".to_owned())),
+        }
+    }
+
+    async fn sign_digest(
+        &self,
+        digest: [u8; 32],
+        public_key: &PublicKey,
+    ) -> Result<RecoverableSignature, SignerError> {
+        let request = Request::SignDigest {
+            digest: hex::encode(digest),
+            public_key: public_key.to_wif(),
+        };
+
+        let signature = match self.request(&request).await? {
+            Response::Signature(signature) => hex::decode(signature)
+                .ok()
+                .and_then(|bytes| signature_from_bytes(&bytes))
+                .ok_or(SignerError::InvalidSignature)?,
+            _ => return Err(SignerError::Remote("Unexpected response".to_owned())),
+        };
+
+        // Don't trust the other side blindly, the signature has to be made by the requested key
+        let secp = secp256k1::Secp256k1::verification_only();
+        let message = Message::from_slice(&digest).unwrap();
+
+        match secp.recover_ecdsa(&message, &signature) {
+            Ok(key) if key == public_key.key => Ok(signature),
+            _ => Err(SignerError::InvalidSignature),
+        }
+    }
+}
+
+/// Handles a single `Request`
+async fn handle<S: Signer + ?Sized>(signer: &S, request: Request) -> Response {
+    let result = match request {
+        Request::PublicKeys => signer.public_keys().await.map(Response::PublicKeys),
+        Request::SignDigest { digest, public_key } => {
+            let mut bytes = [0u8; 32];
+
+            match (
+                hex::decode_to_slice(digest, &mut bytes),
+                PublicKey::from_wif(public_key),
+            ) {
+                (Ok(()), Ok(public_key)) => {
+                    signer
+                        .sign_digest(bytes, &public_key)
+                        .await
+                        .map(|signature| {
+                            Response::Signature(hex::encode(signature_to_bytes(&signature)))
+                        })
+                }
+                _ => Err(SignerError::Remote("Malformed request".to_owned())),
+            }
+        }
+    };
+
+    result.unwrap_or_else(|e| Response::Error(error_chain(&e)))
+}
+
+/// The message of an error followed by those of its sources, for the client that only gets text
+fn error_chain(e: &dyn std::error::Error) -> String {
+    let mut message = e.to_string();
+    let mut source = e.source();
+
+    while let Some(e) = source {
+        message.push_str(": ");
+        message.push_str(&e.to_string());
+        source = e.source();
+    }
+
+    message
+}
+
+async fn serve_connection<S: Signer + ?Sized>(
+    signer: &S,
+    stream: UnixStream,
+) -> Result<(), SignerError> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        let response = match serde_json::from_str(&line) {
+            Ok(request) => handle(signer, request).await,
+            Err(e) => Response::Error(error_chain(&e)),
+        };
+
+        let mut line = serde_json::to_string(&response)?;
+        line.push('\n');
+        writer.write_all(line.as_bytes()).await?;
+    }
+
+    Ok(())
+}
+
+/// Makes `signer` available to `RemoteSigner`s connecting to `listener`. Runs until accepting a
+/// connection fails, every connection is handled on its own task.
+///
+/// # Arguments
+/// * `listener` - Unix socket to accept connections on
+/// * `signer` - Signer that creates the signatures
+pub async fn serve<S: Signer + 'static>(
+    listener: UnixListener,
+    signer: Arc<S>,
+) -> Result<(), SignerError> {
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let signer = signer.clone();
+
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(signer.as_ref(), stream).await {
+                tracing::warn!("Remote signer connection failed: {:?}", e);
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::private_key::PrivateKey;
+    use crate::signer::remote::{serve, RemoteSigner};
+    use crate::signer::tests::unsigned_transaction;
+    use crate::signer::{Signer, SignerError};
+    use std::sync::Arc;
+    use tokio::net::UnixListener;
+
+    #[tokio::test]
+    async fn remote_signer() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("signer.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+        let key = PrivateKey::from_seed("alice").unwrap();
+        tokio::spawn(serve(listener, Arc::new(key)));
+
+        let signer = RemoteSigner::new(&path);
+        let public_keys = signer.public_keys().await.unwrap();
+        assert_eq!(public_keys.len(), 1);
+        assert_eq!(
+            public_keys,
+            vec![PrivateKey::from_seed("alice").unwrap().create_public(None)]
+        );
+
+        let signed = unsigned_transaction()
+            .sign_with_signer(&signer, &public_keys, None)
+            .await
+            .unwrap();
+        assert_eq!(signed.signatures.len(), 1);
+
+        let other = PrivateKey::from_seed("bob").unwrap().create_public(None);
+        assert!(matches!(
+            signer.sign_digest([1u8; 32], &other).await,
+            Err(SignerError::Remote(_))
+        ));
+    }
+}
@@ -0,0 +1,111 @@
+//! Status of recent transactions, from nodes running the `transaction_status_api` plugin.
+
+use crate::client::{request, HiveClient};
+use crate::Error;
+use chrono::{DateTime, Utc};
+use jsonrpsee::core::traits::ToRpcParams;
+use serde::Deserialize;
+use serde_json::json;
+use serde_json::value::RawValue;
+
+struct FindTransactionParams<'a> {
+    transaction_id: &'a str,
+    expiration: Option<DateTime<Utc>>,
+}
+
+impl ToRpcParams for FindTransactionParams<'_> {
+    fn to_rpc_params(self) -> Result<Option<Box<RawValue>>, jsonrpsee::core::Error> {
+        let mut params = json!({ "transaction_id": self.transaction_id });
+        if let Some(expiration) = self.expiration {
+            params["expiration"] = json!(expiration.format("%Y-%m-%dT%H:%M:%S").to_string());
+        }
+
+        Ok(Some(RawValue::from_string(params.to_string())?))
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionStatus {
+    /// The node does not know the transaction, it was not broadcast or is older than the
+    /// transactions the node tracks
+    Unknown,
+    /// Not included in a block yet
+    WithinMempool,
+    WithinReversibleBlock,
+    WithinIrreversibleBlock,
+    /// Expired without being included, a fork can still include it
+    ExpiredReversible,
+    /// Expired without being included
+    ExpiredIrreversible,
+    /// The expiration is before the transactions the node tracks
+    TooOld,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct FindTransactionResponse {
+    pub status: TransactionStatus,
+    /// Block that includes the transaction, only for the `Within*Block` statuses
+    #[serde(default)]
+    pub block_num: Option<u32>,
+}
+
+/// Looks up where a transaction is, e.g. after broadcasting it
+///
+/// # Arguments
+/// * `client` - Client to request the status with
+/// * `transaction_id` - Id of the transaction, see `Transaction::id`
+/// * `expiration` - Expiration of the transaction, lets the node tell an expired transaction from
+///   an unknown one
+pub async fn find_transaction<C: HiveClient + ?Sized>(
+    client: &C,
+    transaction_id: &str,
+    expiration: Option<DateTime<Utc>>,
+) -> Result<FindTransactionResponse, Error> {
+    let params = FindTransactionParams {
+        transaction_id,
+        expiration,
+    };
+    let response: FindTransactionResponse =
+        request(client, "transaction_status_api.find_transaction", params).await?;
+
+    Ok(response)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::client::MockClient;
+    use crate::transaction_status_api::{find_transaction, TransactionStatus};
+    use chrono::{TimeZone, Utc};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn find() {
+        let client = MockClient::new();
+        client.respond(
+            "transaction_status_api.find_transaction",
+            json!({ "status": "within_irreversible_block", "block_num": 100 }),
+        );
+
+        let expiration = Utc.with_ymd_and_hms(2022, 10, 20, 10, 10, 0).unwrap();
+        let response = find_transaction(&client, "abcd", Some(expiration))
+            .await
+            .unwrap();
+
+        assert_eq!(response.status, TransactionStatus::WithinIrreversibleBlock);
+        assert_eq!(response.block_num, Some(100));
+        assert_eq!(
+            client.requests()[0].1,
+            json!({ "transaction_id": "abcd", "expiration": "2022-10-20T10:10:00" })
+        );
+
+        client.respond(
+            "transaction_status_api.find_transaction",
+            json!({ "status": "within_mempool" }),
+        );
+        let response = find_transaction(&client, "abcd", None).await.unwrap();
+        assert_eq!(response.status, TransactionStatus::WithinMempool);
+        assert_eq!(response.block_num, None);
+        assert_eq!(client.requests()[1].1, json!({ "transaction_id": "abcd" }));
+    }
+}
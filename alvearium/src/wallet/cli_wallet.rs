@@ -0,0 +1,146 @@
+use crate::crypto::sha512;
+use crate::wallet::WalletError;
+use aes::cipher::block_padding::Pkcs7;
+use aes::cipher::{BlockDecryptMut, KeyIvInit};
+use serde::Deserialize;
+use std::io::Read;
+use zeroize::Zeroizing;
+
+type Aes256CbcDec = cbc::Decryptor<aes::Aes256>;
+
+/// The part of cli_wallet's `wallet.json` that holds the keys
+#[derive(Deserialize)]
+struct CliWalletData {
+    cipher_keys: String,
+}
+
+fn read_leb128(reader: &mut &[u8]) -> Result<u64, WalletError> {
+    leb128::read::unsigned(reader).map_err(|_| WalletError::InvalidFormat)
+}
+
+fn read_bytes<'a>(reader: &mut &'a [u8], n: usize) -> Result<&'a [u8], WalletError> {
+    if reader.len() < n {
+        return Err(WalletError::InvalidFormat);
+    }
+
+    let (bytes, rest) = reader.split_at(n);
+    *reader = rest;
+
+    Ok(bytes)
+}
+
+/// Decrypts the keys of a cli_wallet `wallet.json` and returns them in WIF format.
+///
+/// cli_wallet encrypts the packed `plain_keys` struct using AES-256-CBC, where both the key and the
+/// IV are taken from the SHA-512 of the password. The struct contains a map of public key to WIF
+/// and the SHA-512 of the password as checksum.
+/// https://gitlab.syncad.com/hive/hive/-/blob/master/libraries/wallet/wallet.cpp
+///
+/// # Arguments
+/// * `json` - Contents of the `wallet.json`
+/// * `password` - Password of the cli_wallet
+pub(crate) fn decrypt_keys(
+    json: &str,
+    password: &str,
+) -> Result<Vec<Zeroizing<String>>, WalletError> {
+    let data: CliWalletData = serde_json::from_str(json)?;
+    let cipher_keys = hex::decode(data.cipher_keys).map_err(|_| WalletError::InvalidFormat)?;
+    let password_hash = Zeroizing::new(sha512(password));
+
+    let plain_keys = Zeroizing::new(
+        Aes256CbcDec::new(password_hash[0..32].into(), password_hash[32..48].into())
+            .decrypt_padded_vec_mut::<Pkcs7>(&cipher_keys)
+            .map_err(|_| WalletError::InvalidPassword)?,
+    );
+
+    let mut reader = plain_keys.as_slice();
+    let count = read_leb128(&mut reader)?;
+    let mut keys = vec![];
+
+    for _ in 0..count {
+        // Compressed public key, it is derived from the private key again when importing
+        read_bytes(&mut reader, 33)?;
+
+        let len = read_leb128(&mut reader)? as usize;
+        let mut wif = Zeroizing::new(String::new());
+        read_bytes(&mut reader, len)?
+            .read_to_string(&mut wif)
+            .map_err(|_| WalletError::InvalidFormat)?;
+
+        keys.push(wif);
+    }
+
+    if read_bytes(&mut reader, 64)? != password_hash.as_slice() {
+        return Err(WalletError::InvalidPassword);
+    }
+
+    Ok(keys)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::private_key::PrivateKey;
+    use crate::crypto::FromWif;
+    use crate::wallet::cli_wallet::decrypt_keys;
+    use crate::wallet::{KdfParams, Wallet, WalletError};
+
+    // wallet.json with the keys for the seeds "alice" and "bob", encrypted with "alvearium"
+    const CLI_WALLET: &str = concat!(
+        r#"{"cipher_keys":""#,
+        "a904a1bd1d5b63b73f738d6979fec3c0b5ca584c2457767f4b37421b0a312ba2",
+        "22d39fe8dfe7be8acbd25028f6ecf103fd6e1db28364d08053f337f817ccf21f",
+        "199f855575837ee74c0905de8b642cd00de5af4d72c41ed519f4ce0884b8d844",
+        "67c4f16ea25623ec84caed11b091ed55c8cbbe869432d4b72e52500ace18eab6",
+        "d1071f90ec7d2eafea7bdf44d41e8c107c853334e83b06cc0be4f895b5fd47f1",
+        "2dee38fd6971d9a24974e8e996a3327df4bf241880e84f518708e432433d3f4d",
+        "324f9f6242a3387d291ab00d793a4e52ae6b7c753fb7ee50b40e265c46920361",
+        "faff3229262b417431b3cd3e4d557c00",
+        r#"","ws_server":"ws://localhost:8090","ws_user":"","ws_password":""}"#
+    );
+
+    #[test]
+    fn decrypt_cli_wallet() {
+        let keys = decrypt_keys(CLI_WALLET, "alvearium").unwrap();
+
+        assert_eq!(keys.len(), 2);
+        assert_eq!(
+            keys[0].as_str(),
+            "5J9bWm2ThenDm3tjvmUgHtWCVMUdjRR1pxnRtnJjvKA4b2ut5WK"
+        );
+        assert_eq!(
+            keys[1].as_str(),
+            "5JoQtsKQuH8hC9MyvfJAqo6qmKLm8ePYNucs7tPu2YxG12trzBt"
+        );
+    }
+
+    #[test]
+    fn decrypt_cli_wallet_wrong_password() {
+        assert!(matches!(
+            decrypt_keys(CLI_WALLET, "not the password"),
+            Err(WalletError::InvalidPassword)
+        ));
+    }
+
+    #[test]
+    fn import_cli_wallet() {
+        let params = KdfParams {
+            log_n: 4,
+            r: 8,
+            p: 1,
+        };
+        let mut wallet = Wallet::with_kdf_params("hunter2", params).unwrap();
+
+        assert_eq!(
+            wallet.import_cli_wallet(CLI_WALLET, "alvearium").unwrap(),
+            2
+        );
+
+        let alice = PrivateKey::from_wif("5J9bWm2ThenDm3tjvmUgHtWCVMUdjRR1pxnRtnJjvKA4b2ut5WK");
+        assert_eq!(
+            wallet
+                .key_for(&PrivateKey::from_seed("alice").unwrap().create_public(None))
+                .unwrap(),
+            Some(&alice.unwrap())
+        );
+    }
+}
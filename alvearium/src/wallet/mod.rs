@@ -0,0 +1,591 @@
+mod cli_wallet;
+
+use crate::condenser_api::transaction::{Transaction, UnsignedTransaction};
+use crate::crypto::private_key::{PrivateKey, PrivateKeyBuildError};
+use crate::crypto::public_key::PublicKey;
+use crate::crypto::{FromWif, IntoWif, KeyRole};
+use crate::enc::EncodeError;
+use chacha20poly1305::aead::{Aead, KeyInit};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+use zeroize::{Zeroize, Zeroizing};
+
+const WALLET_VERSION: u32 = 1;
+
+#[derive(Debug)]
+pub enum WalletError {
+    Locked,
+    InvalidPassword,
+    NoKeys,
+    UnsupportedVersion(u32),
+    InvalidFormat,
+    InvalidKdfParams,
+    PrivateKey(PrivateKeyBuildError),
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    /// The transaction to sign cannot be encoded
+    Encode(EncodeError),
+}
+
+impl Display for WalletError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WalletError::Locked => f.write_str("wallet is locked"),
+            WalletError::InvalidPassword => f.write_str("invalid wallet password"),
+            WalletError::NoKeys => f.write_str("no keys in the wallet for this account and role"),
+            WalletError::UnsupportedVersion(version) => {
+                write!(f, "unsupported wallet version {}", version)
+            }
+            WalletError::InvalidFormat => f.write_str("wallet file is malformed"),
+            WalletError::InvalidKdfParams => f.write_str("invalid key derivation parameters"),
+            WalletError::PrivateKey(_) => f.write_str("invalid private key in wallet"),
+            WalletError::Io(_) => f.write_str("failed to read or write wallet"),
+            WalletError::Json(_) => f.write_str("failed to parse wallet"),
+            WalletError::Encode(_) => f.write_str("cannot encode transaction"),
+        }
+    }
+}
+
+impl std::error::Error for WalletError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            WalletError::PrivateKey(e) => Some(e),
+            WalletError::Io(e) => Some(e),
+            WalletError::Json(e) => Some(e),
+            WalletError::Encode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for WalletError {
+    fn from(e: std::io::Error) -> Self {
+        WalletError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for WalletError {
+    fn from(e: serde_json::Error) -> Self {
+        WalletError::Json(e)
+    }
+}
+
+impl From<EncodeError> for WalletError {
+    fn from(e: EncodeError) -> Self {
+        WalletError::Encode(e)
+    }
+}
+
+impl From<PrivateKeyBuildError> for WalletError {
+    fn from(e: PrivateKeyBuildError) -> Self {
+        WalletError::PrivateKey(e)
+    }
+}
+
+/// Parameters for the scrypt key derivation, these are stored in the wallet file so they can be
+/// changed without breaking existing wallets.
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KdfParams {
+    pub log_n: u8,
+    pub r: u32,
+    pub p: u32,
+}
+
+impl Default for KdfParams {
+    fn default() -> Self {
+        Self {
+            log_n: 15,
+            r: 8,
+            p: 1,
+        }
+    }
+}
+
+/// On disk representation of the wallet, only the keys are encrypted
+#[derive(Deserialize, Serialize, Debug, Clone)]
+struct WalletFile {
+    version: u32,
+    kdf: KdfParams,
+    salt: String,
+    nonce: String,
+    cipher_text: String,
+}
+
+/// A key as stored in the encrypted part of the wallet file
+#[derive(Deserialize, Serialize)]
+struct StoredKey {
+    wif: String,
+    labels: BTreeSet<KeyLabel>,
+}
+
+impl Drop for StoredKey {
+    fn drop(&mut self) {
+        self.wif.zeroize();
+    }
+}
+
+/// Account and role a key is used for
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct KeyLabel {
+    pub account: Option<String>,
+    pub role: Option<KeyRole>,
+}
+
+impl KeyLabel {
+    /// Label without account nor role, it is not stored
+    fn is_empty(&self) -> bool {
+        self.account.is_none() && self.role.is_none()
+    }
+}
+
+/// A private key in the wallet, labeled with the accounts and roles it is used for. The same key
+/// can be used by several accounts or for several roles of an account.
+#[derive(Debug, Clone)]
+pub struct WalletKey {
+    pub key: PrivateKey,
+    pub public_key: PublicKey,
+    pub labels: BTreeSet<KeyLabel>,
+}
+
+struct Unlocked {
+    encryption_key: Zeroizing<[u8; 32]>,
+    keys: Vec<WalletKey>,
+}
+
+/// A password encrypted collection of private keys.
+///
+/// The keys are encrypted using XChaCha20-Poly1305 with a key derived from the password using
+/// scrypt. While the wallet is unlocked the keys and the derived key are kept in memory, every
+/// change re-encrypts the wallet so it can be saved at any time.
+pub struct Wallet {
+    file: WalletFile,
+    unlocked: Option<Unlocked>,
+}
+
+fn derive_key(password: &str, salt: &[u8], params: &KdfParams) -> Result<[u8; 32], WalletError> {
+    let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p)
+        .map_err(|_| WalletError::InvalidKdfParams)?;
+    let mut key = [0u8; 32];
+
+    scrypt::scrypt(password.as_bytes(), salt, &scrypt_params, &mut key)
+        .map_err(|_| WalletError::InvalidKdfParams)?;
+
+    Ok(key)
+}
+
+impl Wallet {
+    /// Creates a new, empty and unlocked, wallet with the default key derivation parameters
+    ///
+    /// # Arguments
+    /// * `password` - Password used to encrypt the wallet
+    pub fn new(password: &str) -> Result<Self, WalletError> {
+        Self::with_kdf_params(password, KdfParams::default())
+    }
+
+    /// Creates a new, empty and unlocked, wallet
+    ///
+    /// # Arguments
+    /// * `password` - Password used to encrypt the wallet
+    /// * `params` - Parameters for the scrypt key derivation
+    pub fn with_kdf_params(password: &str, params: KdfParams) -> Result<Self, WalletError> {
+        let mut salt = [0u8; 16];
+        OsRng.fill_bytes(&mut salt);
+
+        let encryption_key = Zeroizing::new(derive_key(password, &salt, &params)?);
+        let mut wallet = Self {
+            file: WalletFile {
+                version: WALLET_VERSION,
+                kdf: params,
+                salt: hex::encode(salt),
+                nonce: String::new(),
+                cipher_text: String::new(),
+            },
+            unlocked: Some(Unlocked {
+                encryption_key,
+                keys: vec![],
+            }),
+        };
+
+        wallet.encrypt()?;
+
+        Ok(wallet)
+    }
+
+    /// Reads a locked wallet from its JSON representation
+    pub fn from_json(json: &str) -> Result<Self, WalletError> {
+        let file: WalletFile = serde_json::from_str(json)?;
+
+        if file.version != WALLET_VERSION {
+            return Err(WalletError::UnsupportedVersion(file.version));
+        }
+
+        Ok(Self {
+            file,
+            unlocked: None,
+        })
+    }
+
+    /// Returns the JSON representation of the wallet, the keys are always encrypted
+    pub fn to_json(&self) -> Result<String, WalletError> {
+        Ok(serde_json::to_string_pretty(&self.file)?)
+    }
+
+    /// Reads a locked wallet from a file
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, WalletError> {
+        Self::from_json(&std::fs::read_to_string(path)?)
+    }
+
+    /// Writes the wallet to a file. The file is written next to the destination first and then
+    /// moved into place, so a failed write never leaves a corrupted wallet behind.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), WalletError> {
+        let path = path.as_ref();
+        let mut temporary = path.as_os_str().to_owned();
+        temporary.push(".tmp");
+
+        std::fs::write(&temporary, self.to_json()?)?;
+        std::fs::rename(&temporary, path)?;
+
+        Ok(())
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.unlocked.is_none()
+    }
+
+    /// Decrypts the keys in the wallet
+    ///
+    /// # Arguments
+    /// * `password` - Password the wallet was created with
+    pub fn unlock(&mut self, password: &str) -> Result<(), WalletError> {
+        let salt = hex::decode(&self.file.salt).map_err(|_| WalletError::InvalidFormat)?;
+        let nonce = hex::decode(&self.file.nonce).map_err(|_| WalletError::InvalidFormat)?;
+        let cipher_text =
+            hex::decode(&self.file.cipher_text).map_err(|_| WalletError::InvalidFormat)?;
+
+        if nonce.len() != 24 {
+            return Err(WalletError::InvalidFormat);
+        }
+
+        let encryption_key = Zeroizing::new(derive_key(password, &salt, &self.file.kdf)?);
+        let plain_text = Zeroizing::new(
+            XChaCha20Poly1305::new(encryption_key.as_ref().into())
+                .decrypt(XNonce::from_slice(&nonce), cipher_text.as_slice())
+                .map_err(|_| WalletError::InvalidPassword)?,
+        );
+
+        let stored: Vec<StoredKey> = serde_json::from_slice(&plain_text)?;
+        let keys = stored
+            .iter()
+            .map(|stored| {
+                let key = PrivateKey::from_wif(&stored.wif)?;
+
+                Ok(WalletKey {
+                    public_key: key.create_public(None),
+                    key,
+                    labels: stored.labels.clone(),
+                })
+            })
+            .collect::<Result<Vec<_>, WalletError>>()?;
+
+        self.unlocked = Some(Unlocked {
+            encryption_key,
+            keys,
+        });
+
+        Ok(())
+    }
+
+    /// Removes the decrypted keys from memory
+    pub fn lock(&mut self) {
+        self.unlocked = None;
+    }
+
+    /// Adds a key to the wallet. If the key is already present the account and role are added to
+    /// its labels.
+    ///
+    /// # Arguments
+    /// * `key` - Key to add
+    /// * `account` - Account the key belongs to, if known
+    /// * `role` - Role of the key for `account`, if known
+    pub fn import_key(
+        &mut self,
+        key: PrivateKey,
+        account: Option<&str>,
+        role: Option<KeyRole>,
+    ) -> Result<PublicKey, WalletError> {
+        let label = KeyLabel {
+            account: account.map(|account| account.to_owned()),
+            role,
+        };
+
+        Ok(self.import_keys([(key, label)])?.remove(0))
+    }
+
+    /// Adds keys to the wallet, encrypting it once for all of them. Keys that are already present
+    /// get the labels added.
+    ///
+    /// # Arguments
+    /// * `keys` - Keys to add with the account and role they are used for
+    pub fn import_keys(
+        &mut self,
+        keys: impl IntoIterator<Item = (PrivateKey, KeyLabel)>,
+    ) -> Result<Vec<PublicKey>, WalletError> {
+        let unlocked = self.unlocked.as_mut().ok_or(WalletError::Locked)?;
+        let mut public_keys = vec![];
+
+        for (key, label) in keys {
+            let public_key = key.create_public(None);
+            let index = match unlocked
+                .keys
+                .iter()
+                .position(|existing| existing.key == key)
+            {
+                Some(index) => index,
+                None => {
+                    unlocked.keys.push(WalletKey {
+                        public_key: public_key.clone(),
+                        key,
+                        labels: BTreeSet::new(),
+                    });
+                    unlocked.keys.len() - 1
+                }
+            };
+
+            if !label.is_empty() {
+                unlocked.keys[index].labels.insert(label);
+            }
+            public_keys.push(public_key);
+        }
+
+        self.encrypt()?;
+
+        Ok(public_keys)
+    }
+
+    /// Imports all keys of a cli_wallet `wallet.json`. cli_wallet does not store which account a
+    /// key belongs to, so the keys are imported without account and role.
+    ///
+    /// # Arguments
+    /// * `json` - Contents of the `wallet.json`
+    /// * `password` - Password of the cli_wallet
+    pub fn import_cli_wallet(&mut self, json: &str, password: &str) -> Result<usize, WalletError> {
+        if self.is_locked() {
+            return Err(WalletError::Locked);
+        }
+
+        let keys = cli_wallet::decrypt_keys(json, password)?
+            .iter()
+            .map(|wif| {
+                let label = KeyLabel {
+                    account: None,
+                    role: None,
+                };
+
+                Ok((PrivateKey::from_wif(wif.as_str())?, label))
+            })
+            .collect::<Result<Vec<_>, WalletError>>()?;
+
+        Ok(self.import_keys(keys)?.len())
+    }
+
+    pub fn keys(&self) -> Result<&[WalletKey], WalletError> {
+        let unlocked = self.unlocked.as_ref().ok_or(WalletError::Locked)?;
+
+        Ok(&unlocked.keys)
+    }
+
+    /// Returns the keys of `account` for `role`
+    pub fn keys_for(&self, account: &str, role: KeyRole) -> Result<Vec<&PrivateKey>, WalletError> {
+        Ok(self
+            .keys()?
+            .iter()
+            .filter(|key| {
+                key.labels.iter().any(|label| {
+                    label.account.as_deref() == Some(account) && label.role == Some(role)
+                })
+            })
+            .map(|key| &key.key)
+            .collect())
+    }
+
+    /// Returns the private key belonging to `public_key`, if it is in the wallet
+    pub fn key_for(&self, public_key: &PublicKey) -> Result<Option<&PrivateKey>, WalletError> {
+        Ok(self
+            .keys()?
+            .iter()
+            .find(|key| key.public_key == *public_key)
+            .map(|key| &key.key))
+    }
+
+    /// Signs a transaction with all keys of `account` for `role`
+    ///
+    /// # Arguments
+    /// * `transaction` - Transaction to sign
+    /// * `account` - Account to sign for
+    /// * `role` - Role needed for the operations in the transaction
+    /// * `chain_id` - Chain to sign for, mainnet if `None`
+    pub fn sign_transaction(
+        &self,
+        transaction: UnsignedTransaction,
+        account: &str,
+        role: KeyRole,
+        chain_id: Option<[u8; 32]>,
+    ) -> Result<Transaction, WalletError> {
+        let keys = self.keys_for(account, role)?;
+
+        if keys.is_empty() {
+            return Err(WalletError::NoKeys);
+        }
+
+        Ok(transaction.sign_with_keys(&keys, chain_id)?)
+    }
+
+    fn encrypt(&mut self) -> Result<(), WalletError> {
+        let unlocked = self.unlocked.as_ref().ok_or(WalletError::Locked)?;
+        let stored: Vec<StoredKey> = unlocked
+            .keys
+            .iter()
+            .map(|key| StoredKey {
+                wif: key.key.to_wif(),
+                labels: key.labels.clone(),
+            })
+            .collect();
+        let plain_text = Zeroizing::new(serde_json::to_vec(&stored)?);
+
+        let mut nonce = [0u8; 24];
+        OsRng.fill_bytes(&mut nonce);
+
+        let cipher_text = XChaCha20Poly1305::new(unlocked.encryption_key.as_ref().into())
+            .encrypt(XNonce::from_slice(&nonce), plain_text.as_slice())
+            .map_err(|_| WalletError::InvalidFormat)?;
+
+        self.file.nonce = hex::encode(nonce);
+        self.file.cipher_text = hex::encode(cipher_text);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::crypto::private_key::PrivateKey;
+    use crate::crypto::{IntoWif, KeyRole};
+    use crate::wallet::{KdfParams, KeyLabel, Wallet, WalletError};
+
+    // Cheap parameters so the tests don't spend their time in scrypt
+    const TEST_PARAMS: KdfParams = KdfParams {
+        log_n: 4,
+        r: 8,
+        p: 1,
+    };
+
+    #[test]
+    fn save_load_unlock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("wallet.json");
+
+        let mut wallet = Wallet::with_kdf_params("hunter2", TEST_PARAMS).unwrap();
+        wallet
+            .import_key(
+                PrivateKey::from_seed("alice").unwrap(),
+                Some("alice"),
+                Some(KeyRole::Posting),
+            )
+            .unwrap();
+        wallet
+            .import_key(PrivateKey::from_seed("bob").unwrap(), None, None)
+            .unwrap();
+        wallet.save(&path).unwrap();
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(!contents.contains(&PrivateKey::from_seed("alice").unwrap().to_wif()));
+
+        let mut wallet = Wallet::load(&path).unwrap();
+        assert!(wallet.is_locked());
+        assert!(matches!(wallet.keys(), Err(WalletError::Locked)));
+
+        wallet.unlock("hunter2").unwrap();
+        assert_eq!(wallet.keys().unwrap().len(), 2);
+
+        let posting = wallet.keys_for("alice", KeyRole::Posting).unwrap();
+        assert_eq!(posting, vec![&PrivateKey::from_seed("alice").unwrap()]);
+        assert!(wallet
+            .keys_for("alice", KeyRole::Active)
+            .unwrap()
+            .is_empty());
+
+        let bob = PrivateKey::from_seed("bob").unwrap();
+        assert_eq!(
+            wallet.key_for(&bob.create_public(None)).unwrap(),
+            Some(&bob)
+        );
+
+        wallet.lock();
+        assert!(wallet.is_locked());
+    }
+
+    #[test]
+    fn wrong_password() {
+        let wallet = Wallet::with_kdf_params("hunter2", TEST_PARAMS).unwrap();
+        let mut wallet = Wallet::from_json(&wallet.to_json().unwrap()).unwrap();
+
+        assert!(matches!(
+            wallet.unlock("hunter3"),
+            Err(WalletError::InvalidPassword)
+        ));
+        assert!(wallet.is_locked());
+    }
+
+    #[test]
+    fn import_updates_existing_key() {
+        let mut wallet = Wallet::with_kdf_params("hunter2", TEST_PARAMS).unwrap();
+        let key = || PrivateKey::from_seed("alice").unwrap();
+
+        wallet.import_key(key(), None, None).unwrap();
+        wallet
+            .import_key(key(), Some("alice"), Some(KeyRole::Active))
+            .unwrap();
+
+        wallet
+            .import_key(key(), Some("alice"), Some(KeyRole::Posting))
+            .unwrap();
+
+        assert_eq!(wallet.keys().unwrap().len(), 1);
+        assert_eq!(wallet.keys().unwrap()[0].labels.len(), 2);
+        // The key keeps the roles it was imported for before
+        assert_eq!(wallet.keys_for("alice", KeyRole::Active).unwrap().len(), 1);
+        assert_eq!(wallet.keys_for("alice", KeyRole::Posting).unwrap().len(), 1);
+
+        let json = wallet.to_json().unwrap();
+        let mut wallet = Wallet::from_json(&json).unwrap();
+        wallet.unlock("hunter2").unwrap();
+        assert_eq!(wallet.keys_for("alice", KeyRole::Active).unwrap().len(), 1);
+        assert_eq!(wallet.keys_for("alice", KeyRole::Posting).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn import_keys_at_once() {
+        let mut wallet = Wallet::with_kdf_params("hunter2", TEST_PARAMS).unwrap();
+        let nonce = wallet.file.nonce.clone();
+        let label = |role| KeyLabel {
+            account: Some("alice".to_owned()),
+            role: Some(role),
+        };
+
+        let public_keys = wallet
+            .import_keys(
+                KeyRole::ALL
+                    .map(|role| (PrivateKey::from_seed(role.as_str()).unwrap(), label(role))),
+            )
+            .unwrap();
+
+        assert_eq!(public_keys.len(), 4);
+        assert_eq!(wallet.keys_for("alice", KeyRole::Memo).unwrap().len(), 1);
+        assert_ne!(wallet.file.nonce, nonce);
+    }
+}